@@ -1,7 +1,7 @@
 use sp_core::{Pair, Public, sr25519};
 use node_template_runtime::{
 	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	KittiesConfig, SudoConfig, SystemConfig, WASM_BINARY, Signature
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
@@ -153,5 +153,16 @@ fn testnet_genesis(
 			// Assign network admin rights.
 			key: root_key,
 		}),
+		pallet_kitties: Some(KittiesConfig {
+			// The first two accounts own a gen-0 kitty each, the first one also owns their child.
+			kitties: match (endowed_accounts.get(0), endowed_accounts.get(1)) {
+				(Some(first), Some(second)) => vec![
+					(first.clone(), [0x11; 16], None),
+					(second.clone(), [0x22; 16], None),
+					(first.clone(), [0x12; 16], Some((1, 2))),
+				],
+				_ => vec![],
+			},
+		}),
 	}
 }
@@ -0,0 +1,151 @@
+//! RPC interface for the kitties pallet.
+
+use std::sync::Arc;
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+
+pub use pallet_kitties_rpc_runtime_api::{KittiesApi as KittiesRuntimeApi, Kitty, KittyLineage, KittyMetadata, KittyTraits};
+
+/// Kitties RPC methods, served under the `kitties_*` namespace.
+#[rpc]
+pub trait KittiesApi<BlockHash, AccountId, KittyIndex, BlockNumber> {
+	/// All kitties owned by `account`.
+	#[rpc(name = "kitties_accountKitties")]
+	fn account_kitties(
+		&self,
+		account: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<(KittyIndex, Kitty<BlockNumber>)>>;
+
+	/// The kitty with the given id.
+	#[rpc(name = "kitties_kitty")]
+	fn kitty(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<Kitty<BlockNumber>>>;
+
+	/// The owner of the kitty with the given id.
+	#[rpc(name = "kitties_owner")]
+	fn kitty_owner(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<AccountId>>;
+
+	/// Parents and children of the kitty with the given id.
+	#[rpc(name = "kitties_lineage")]
+	fn kitty_lineage(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<KittyLineage<KittyIndex>>;
+
+	/// Traits decoded from the DNA of the kitty with the given id.
+	#[rpc(name = "kitties_traits")]
+	fn kitty_traits(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<KittyTraits>>;
+
+	/// Name, description and image URI of the kitty with the given id.
+	#[rpc(name = "kitties_metadata")]
+	fn kitty_metadata(&self, kitty_id: KittyIndex, at: Option<BlockHash>) -> Result<Option<KittyMetadata>>;
+
+	/// The number of kitties ever created.
+	#[rpc(name = "kitties_count")]
+	fn kitties_count(&self, at: Option<BlockHash>) -> Result<KittyIndex>;
+}
+
+/// Implements the [`KittiesApi`] RPC trait by calling into the runtime.
+pub struct Kitties<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Kitties<C, B> {
+	/// Create new `Kitties` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Kitties { client, _marker: Default::default() }
+	}
+}
+
+/// Error code for failed runtime calls.
+const RUNTIME_ERROR: i64 = 1;
+
+fn runtime_error(e: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Unable to query kitties.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, KittyIndex, BlockNumber>
+	KittiesApi<<Block as BlockT>::Hash, AccountId, KittyIndex, BlockNumber> for Kitties<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block>,
+	C::Api: KittiesRuntimeApi<Block, AccountId, KittyIndex, BlockNumber>,
+	AccountId: Codec,
+	KittyIndex: Codec,
+	BlockNumber: Codec,
+{
+	fn account_kitties(
+		&self,
+		account: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<(KittyIndex, Kitty<BlockNumber>)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.account_kitties(&at, account).map_err(runtime_error)
+	}
+
+	fn kitty(
+		&self,
+		kitty_id: KittyIndex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<Kitty<BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitty(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn kitty_owner(
+		&self,
+		kitty_id: KittyIndex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<AccountId>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitty_owner(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn kitty_lineage(
+		&self,
+		kitty_id: KittyIndex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<KittyLineage<KittyIndex>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitty_lineage(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn kitty_traits(
+		&self,
+		kitty_id: KittyIndex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<KittyTraits>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitty_traits(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn kitty_metadata(
+		&self,
+		kitty_id: KittyIndex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<KittyMetadata>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitty_metadata(&at, kitty_id).map_err(runtime_error)
+	}
+
+	fn kitties_count(&self, at: Option<<Block as BlockT>::Hash>) -> Result<KittyIndex> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		api.kitties_count(&at).map_err(runtime_error)
+	}
+}
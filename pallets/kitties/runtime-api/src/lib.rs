@@ -0,0 +1,31 @@
+//! Runtime API definition for the kitties pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+pub use pallet_kitties::{Kitty, KittyLineage, KittyMetadata, KittyTraits};
+
+sp_api::decl_runtime_apis! {
+	pub trait KittiesApi<AccountId, KittyIndex, BlockNumber> where
+		AccountId: Codec,
+		KittyIndex: Codec,
+		BlockNumber: Codec,
+	{
+		/// All kitties owned by `account`.
+		fn account_kitties(account: AccountId) -> Vec<(KittyIndex, Kitty<BlockNumber>)>;
+		/// The kitty with the given id.
+		fn kitty(kitty_id: KittyIndex) -> Option<Kitty<BlockNumber>>;
+		/// The owner of the kitty with the given id.
+		fn kitty_owner(kitty_id: KittyIndex) -> Option<AccountId>;
+		/// Parents and children of the kitty with the given id.
+		fn kitty_lineage(kitty_id: KittyIndex) -> KittyLineage<KittyIndex>;
+		/// Traits decoded from the DNA of the kitty with the given id.
+		fn kitty_traits(kitty_id: KittyIndex) -> Option<KittyTraits>;
+		/// Name, description and image URI of the kitty with the given id.
+		fn kitty_metadata(kitty_id: KittyIndex) -> Option<KittyMetadata>;
+		/// The number of kitties ever created.
+		fn kitties_count() -> KittyIndex;
+	}
+}
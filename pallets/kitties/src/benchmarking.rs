@@ -0,0 +1,485 @@
+// pallet_kitties 的 benchmark
+
+use super::*;
+
+use frame_benchmarking::{benchmarks, account, whitelisted_caller};
+use frame_support::traits::OnFinalize;
+use frame_system::RawOrigin;
+use sp_std::vec;
+
+use crate::Module as Kitties;
+
+const SEED: u32 = 0;
+
+// 给账户充足的余额
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+    let who: T::AccountId = account(name, index, SEED);
+    T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 4u32.into());
+    who
+}
+
+fn funded_caller<T: Trait>() -> T::AccountId {
+    let caller: T::AccountId = whitelisted_caller();
+    T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value() / 4u32.into());
+    caller
+}
+
+// 挂单、拍卖、配种用的价格
+fn price<T: Trait>() -> BalanceOf<T> {
+    1_000u32.into()
+}
+
+// 创建一只kitty，返回kitty id
+fn create_kitty<T: Trait>(owner: &T::AccountId) -> T::KittyIndex {
+    Kitties::<T>::create(RawOrigin::Signed(owner.clone()).into()).expect("create kitty should work");
+    Kitties::<T>::kitties_count()
+}
+
+// 创建一只kitty，并且给它 depth 代互不相同的祖先，近亲检查要读完所有祖先，是 breed 最坏的情况
+fn create_kitty_with_ancestry<T: Trait>(owner: &T::AccountId, depth: u32) -> T::KittyIndex {
+    let kitty_id = create_kitty::<T>(owner);
+    if depth > 0 {
+        // 每对祖先放在单独的账户里，避免超过 MaxKittiesPerAccount
+        let ancestor_owner = funded_account::<T>("ancestor", Kitties::<T>::kitties_count().saturated_into());
+        let parent_1 = create_kitty_with_ancestry::<T>(&ancestor_owner, depth - 1);
+        let parent_2 = create_kitty_with_ancestry::<T>(&ancestor_owner, depth - 1);
+        KittyParents::<T>::insert(kitty_id, (parent_1, parent_2));
+    }
+    kitty_id
+}
+
+benchmarks! {
+    _ { }
+
+    slash_deposit {
+        let owner = funded_account::<T>("owner", 0);
+        let dest = funded_account::<T>("dest", 0);
+        let kitty_id = create_kitty::<T>(&owner);
+    }: _(RawOrigin::Root, kitty_id, dest)
+    verify {
+        assert_eq!(T::Currency::reserved_balance(&owner), BalanceOf::<T>::from(0u32));
+    }
+
+    create {
+        let caller = funded_caller::<T>();
+    }: _(RawOrigin::Signed(caller.clone()))
+    verify {
+        assert_eq!(Kitties::<T>::owned_kitties_count(&caller), 1);
+    }
+
+    create_many {
+        let n in 1 .. T::MaxBatchSize::get();
+
+        let caller = funded_caller::<T>();
+    }: _(RawOrigin::Signed(caller.clone()), n)
+    verify {
+        assert_eq!(Kitties::<T>::owned_kitties_count(&caller), n);
+    }
+
+    transfer {
+        let caller = funded_caller::<T>();
+        let to = funded_account::<T>("to", 0);
+        // 拥有者有多只kitty 时，转走第一只需要 swap-remove
+        create_kitty::<T>(&caller);
+        let kitty_id = Kitties::<T>::owned_kitty(&caller, 0).unwrap();
+        create_kitty::<T>(&caller);
+    }: _(RawOrigin::Signed(caller.clone()), to.clone(), kitty_id)
+    verify {
+        assert_eq!(Kitties::<T>::kitty_owner(kitty_id), Some(to));
+    }
+
+    approve {
+        let caller = funded_caller::<T>();
+        let operator = funded_account::<T>("operator", 0);
+        let kitty_id = create_kitty::<T>(&caller);
+    }: _(RawOrigin::Signed(caller), kitty_id, operator.clone())
+    verify {
+        assert_eq!(Kitties::<T>::approved(kitty_id), Some(operator));
+    }
+
+    set_approval_for_all {
+        let caller = funded_caller::<T>();
+        let operator = funded_account::<T>("operator", 0);
+    }: _(RawOrigin::Signed(caller.clone()), operator.clone(), true)
+    verify {
+        assert!(Kitties::<T>::is_approved_for_all(&caller, &operator));
+    }
+
+    transfer_from {
+        let owner = funded_account::<T>("owner", 0);
+        let to = funded_account::<T>("to", 0);
+        let caller = funded_caller::<T>();
+        create_kitty::<T>(&owner);
+        let kitty_id = Kitties::<T>::owned_kitty(&owner, 0).unwrap();
+        create_kitty::<T>(&owner);
+        // 最坏情况：单只授权不匹配，要再查全部授权
+        Kitties::<T>::set_approval_for_all(RawOrigin::Signed(owner.clone()).into(), caller.clone(), true)?;
+    }: _(RawOrigin::Signed(caller), owner, to.clone(), kitty_id)
+    verify {
+        assert_eq!(Kitties::<T>::kitty_owner(kitty_id), Some(to));
+    }
+
+    // 替换别人设置的 metadata，需要退还原来的质押
+    set_metadata {
+        let b in 1 .. T::MaxUriLength::get();
+
+        let owner = funded_account::<T>("owner", 0);
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&owner);
+        let name = vec![b'n'; T::MaxNameLength::get() as usize];
+        let description = vec![b'd'; T::MaxDescriptionLength::get() as usize];
+        Kitties::<T>::set_metadata(RawOrigin::Signed(owner.clone()).into(), kitty_id, name.clone(), description.clone(), vec![b'u'])?;
+        Kitties::<T>::transfer(RawOrigin::Signed(owner).into(), caller.clone(), kitty_id)?;
+        let uri = vec![b'u'; b as usize];
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id, name, description, uri)
+    verify {
+        assert_eq!(Kitties::<T>::metadata_deposit(kitty_id).map(|(who, _)| who), Some(caller));
+    }
+
+    transfer_many {
+        let n in 1 .. T::MaxBatchSize::get();
+
+        let caller = funded_caller::<T>();
+        let mut transfers = Vec::new();
+        for i in 0 .. n {
+            let kitty_id = create_kitty::<T>(&caller);
+            transfers.push((funded_account::<T>("to", i), kitty_id));
+        }
+    }: _(RawOrigin::Signed(caller.clone()), transfers)
+    verify {
+        assert_eq!(Kitties::<T>::owned_kitties_count(&caller), 0);
+    }
+
+    burn {
+        let caller = funded_caller::<T>();
+        create_kitty::<T>(&caller);
+        let kitty_id = Kitties::<T>::owned_kitty(&caller, 0).unwrap();
+        create_kitty::<T>(&caller);
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id)
+    verify {
+        assert!(Kitties::<T>::is_deceased(kitty_id));
+    }
+
+    list {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        let price = price::<T>();
+    }: _(RawOrigin::Signed(caller), kitty_id, price)
+    verify {
+        assert_eq!(Kitties::<T>::kitty_price(kitty_id), Some(price));
+    }
+
+    unlist {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        Kitties::<T>::list(RawOrigin::Signed(caller.clone()).into(), kitty_id, price::<T>())?;
+    }: _(RawOrigin::Signed(caller), kitty_id)
+    verify {
+        assert_eq!(Kitties::<T>::kitty_price(kitty_id), None);
+    }
+
+    buy {
+        let seller = funded_account::<T>("seller", 0);
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&seller);
+        Kitties::<T>::list(RawOrigin::Signed(seller).into(), kitty_id, price::<T>())?;
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id, price::<T>())
+    verify {
+        assert_eq!(Kitties::<T>::kitty_owner(kitty_id), Some(caller));
+    }
+
+    // 同一个区块已经有 MaxAuctionsPerBlock - 1 个拍卖结束
+    start_auction {
+        let end = frame_system::Module::<T>::block_number() + 10u32.into();
+        for i in 1 .. T::MaxAuctionsPerBlock::get() {
+            let seller = funded_account::<T>("seller", i);
+            let kitty_id = create_kitty::<T>(&seller);
+            Kitties::<T>::start_auction(RawOrigin::Signed(seller).into(), kitty_id, price::<T>(), end)?;
+        }
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+    }: _(RawOrigin::Signed(caller), kitty_id, price::<T>(), end)
+    verify {
+        assert!(Kitties::<T>::auction(kitty_id).is_some());
+    }
+
+    bid {
+        let seller = funded_account::<T>("seller", 0);
+        let bidder = funded_account::<T>("bidder", 0);
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&seller);
+        let price = price::<T>();
+        let end = frame_system::Module::<T>::block_number() + 10u32.into();
+        Kitties::<T>::start_auction(RawOrigin::Signed(seller).into(), kitty_id, price, end)?;
+        // 最坏情况：需要退还上一个出价者
+        Kitties::<T>::bid(RawOrigin::Signed(bidder).into(), kitty_id, price)?;
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id, price + price)
+    verify {
+        assert_eq!(Kitties::<T>::auction(kitty_id).unwrap().highest_bid, Some((caller, price + price)));
+    }
+
+    start_dutch_auction {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        let price = price::<T>();
+        let end = frame_system::Module::<T>::block_number() + 10u32.into();
+    }: _(RawOrigin::Signed(caller), kitty_id, price + price, price, end)
+    verify {
+        assert!(Kitties::<T>::dutch_auction(kitty_id).is_some());
+    }
+
+    cancel_dutch_auction {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        let price = price::<T>();
+        let end = frame_system::Module::<T>::block_number() + 10u32.into();
+        Kitties::<T>::start_dutch_auction(RawOrigin::Signed(caller.clone()).into(), kitty_id, price + price, price, end)?;
+    }: _(RawOrigin::Signed(caller), kitty_id)
+    verify {
+        assert!(Kitties::<T>::dutch_auction(kitty_id).is_none());
+    }
+
+    buy_dutch {
+        let seller = funded_account::<T>("seller", 0);
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&seller);
+        let price = price::<T>();
+        let end = frame_system::Module::<T>::block_number() + 10u32.into();
+        Kitties::<T>::start_dutch_auction(RawOrigin::Signed(seller).into(), kitty_id, price + price, price, end)?;
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id, price + price)
+    verify {
+        assert_eq!(Kitties::<T>::kitty_owner(kitty_id), Some(caller));
+    }
+
+    breed {
+        let caller = funded_caller::<T>();
+        let depth = Kitties::<T>::inbreeding_depth();
+        let kitty_id_1 = create_kitty_with_ancestry::<T>(&caller, depth);
+        let kitty_id_2 = create_kitty_with_ancestry::<T>(&caller, depth);
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id_1, kitty_id_2)
+    verify {
+        assert_eq!(Kitties::<T>::owned_kitties_count(&caller), 3);
+    }
+
+    breed_many {
+        let n in 1 .. T::MaxBatchSize::get();
+
+        let caller = funded_caller::<T>();
+        let depth = Kitties::<T>::inbreeding_depth();
+        let mut pairs = Vec::new();
+        for _ in 0 .. n {
+            pairs.push((
+                create_kitty_with_ancestry::<T>(&caller, depth),
+                create_kitty_with_ancestry::<T>(&caller, depth),
+            ));
+        }
+    }: _(RawOrigin::Signed(caller.clone()), pairs)
+    verify {
+        assert_eq!(Kitties::<T>::owned_kitties_count(&caller), 3 * n);
+    }
+
+    offer_sire {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        let expires = frame_system::Module::<T>::block_number() + 10u32.into();
+    }: _(RawOrigin::Signed(caller), kitty_id, price::<T>(), expires)
+    verify {
+        assert!(Kitties::<T>::sire_offer(kitty_id).is_some());
+    }
+
+    withdraw_sire_offer {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        let expires = frame_system::Module::<T>::block_number() + 10u32.into();
+        Kitties::<T>::offer_sire(RawOrigin::Signed(caller.clone()).into(), kitty_id, price::<T>(), expires)?;
+    }: _(RawOrigin::Signed(caller), kitty_id)
+    verify {
+        assert!(Kitties::<T>::sire_offer(kitty_id).is_none());
+    }
+
+    breed_with_sire {
+        let sire_owner = funded_account::<T>("sire_owner", 0);
+        let caller = funded_caller::<T>();
+        let depth = Kitties::<T>::inbreeding_depth();
+        let sire_id = create_kitty_with_ancestry::<T>(&sire_owner, depth);
+        let kitty_id = create_kitty_with_ancestry::<T>(&caller, depth);
+        let expires = frame_system::Module::<T>::block_number() + 10u32.into();
+        Kitties::<T>::offer_sire(RawOrigin::Signed(sire_owner).into(), sire_id, price::<T>(), expires)?;
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id, sire_id, price::<T>())
+    verify {
+        assert_eq!(Kitties::<T>::owned_kitties_count(&caller), 2);
+    }
+
+    offer_rental {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+    }: _(RawOrigin::Signed(caller), kitty_id, price::<T>(), 10u32.into())
+    verify {
+        assert!(Kitties::<T>::rental_offer(kitty_id).is_some());
+    }
+
+    cancel_rental_offer {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        Kitties::<T>::offer_rental(RawOrigin::Signed(caller.clone()).into(), kitty_id, price::<T>(), 10u32.into())?;
+    }: _(RawOrigin::Signed(caller), kitty_id)
+    verify {
+        assert!(Kitties::<T>::rental_offer(kitty_id).is_none());
+    }
+
+    // 同一个区块已经有 MaxRentalsPerBlock - 1 个租借到期
+    rent {
+        let borrower = funded_account::<T>("borrower", 0);
+        for i in 1 .. T::MaxRentalsPerBlock::get() {
+            let owner = funded_account::<T>("owner", i);
+            let kitty_id = create_kitty::<T>(&owner);
+            Kitties::<T>::offer_rental(RawOrigin::Signed(owner).into(), kitty_id, price::<T>(), 10u32.into())?;
+            Kitties::<T>::rent(RawOrigin::Signed(borrower.clone()).into(), kitty_id, price::<T>())?;
+        }
+        let owner = funded_account::<T>("owner", 0);
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&owner);
+        Kitties::<T>::offer_rental(RawOrigin::Signed(owner).into(), kitty_id, price::<T>(), 10u32.into())?;
+    }: _(RawOrigin::Signed(caller.clone()), kitty_id, price::<T>())
+    verify {
+        assert_eq!(Kitties::<T>::custodian(kitty_id), Some(caller));
+    }
+
+    stake {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        Kitties::<T>::list(RawOrigin::Signed(caller.clone()).into(), kitty_id, price::<T>())?;
+    }: _(RawOrigin::Signed(caller), kitty_id)
+    verify {
+        assert!(Kitties::<T>::staked_kitty(kitty_id).is_some());
+    }
+
+    unstake {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        Kitties::<T>::stake(RawOrigin::Signed(caller.clone()).into(), kitty_id)?;
+        frame_system::Module::<T>::set_block_number(frame_system::Module::<T>::block_number() + 10u32.into());
+    }: _(RawOrigin::Signed(caller), kitty_id)
+    verify {
+        assert!(Kitties::<T>::staked_kitty(kitty_id).is_none());
+    }
+
+    claim_rewards {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        Kitties::<T>::stake(RawOrigin::Signed(caller.clone()).into(), kitty_id)?;
+        T::Currency::make_free_balance_be(&Kitties::<T>::account_id(), BalanceOf::<T>::max_value() / 4u32.into());
+        frame_system::Module::<T>::set_block_number(frame_system::Module::<T>::block_number() + 10u32.into());
+    }: _(RawOrigin::Signed(caller.clone()))
+    verify {
+        assert!(Kitties::<T>::staker(&caller).pending.is_zero());
+    }
+
+    reclaim_metadata_deposit {
+        let caller = funded_caller::<T>();
+        let kitty_id = create_kitty::<T>(&caller);
+        let name = vec![b'n'; T::MaxNameLength::get() as usize];
+        Kitties::<T>::set_metadata(RawOrigin::Signed(caller.clone()).into(), kitty_id, name, vec![], vec![])?;
+    }: _(RawOrigin::Signed(caller), kitty_id)
+    verify {
+        assert!(Kitties::<T>::metadata_deposit(kitty_id).is_none());
+    }
+
+    fund_rewards {
+        let amount = price::<T>();
+    }: _(RawOrigin::Root, amount)
+    verify {
+        assert_eq!(T::Currency::free_balance(&Kitties::<T>::account_id()), amount);
+    }
+
+    // 同一个区块归还 r 只租出去的kitty
+    return_rentals {
+        let r in 1 .. T::MaxRentalsPerBlock::get();
+
+        let borrower = funded_account::<T>("borrower", 0);
+        let duration: T::BlockNumber = 10u32.into();
+        for i in 0 .. r {
+            let owner = funded_account::<T>("owner", i);
+            let kitty_id = create_kitty::<T>(&owner);
+            Kitties::<T>::offer_rental(RawOrigin::Signed(owner).into(), kitty_id, price::<T>(), duration)?;
+            Kitties::<T>::rent(RawOrigin::Signed(borrower.clone()).into(), kitty_id, price::<T>())?;
+        }
+        let end = frame_system::Module::<T>::block_number() + duration;
+    }: {
+        Kitties::<T>::return_rentals(end);
+    }
+    verify {
+        assert!(Kitties::<T>::rentals_end_at(end).is_empty());
+        assert!(Kitties::<T>::rental(Kitties::<T>::kitties_count()).is_none());
+    }
+
+    // 同一个区块结束 a 个有出价的拍卖
+    on_finalize {
+        let a in 1 .. T::MaxAuctionsPerBlock::get();
+
+        let price = price::<T>();
+        let end = frame_system::Module::<T>::block_number() + 10u32.into();
+        for i in 0 .. a {
+            let seller = funded_account::<T>("seller", i);
+            let bidder = funded_account::<T>("bidder", i);
+            let kitty_id = create_kitty::<T>(&seller);
+            Kitties::<T>::start_auction(RawOrigin::Signed(seller).into(), kitty_id, price, end)?;
+            Kitties::<T>::bid(RawOrigin::Signed(bidder).into(), kitty_id, price)?;
+        }
+        frame_system::Module::<T>::set_block_number(end);
+    }: {
+        Kitties::<T>::on_finalize(end);
+    }
+    verify {
+        for i in 0 .. a {
+            let bidder = funded_account::<T>("bidder", i);
+            assert_eq!(Kitties::<T>::owned_kitties_count(&bidder), 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{new_test_ext, Test};
+    use frame_support::assert_ok;
+
+    #[test]
+    fn test_benchmarks() {
+        new_test_ext().execute_with(|| {
+            assert_ok!(test_benchmark_slash_deposit::<Test>());
+            assert_ok!(test_benchmark_create::<Test>());
+            assert_ok!(test_benchmark_create_many::<Test>());
+            assert_ok!(test_benchmark_transfer::<Test>());
+            assert_ok!(test_benchmark_transfer_many::<Test>());
+            assert_ok!(test_benchmark_approve::<Test>());
+            assert_ok!(test_benchmark_set_approval_for_all::<Test>());
+            assert_ok!(test_benchmark_transfer_from::<Test>());
+            assert_ok!(test_benchmark_set_metadata::<Test>());
+            assert_ok!(test_benchmark_burn::<Test>());
+            assert_ok!(test_benchmark_list::<Test>());
+            assert_ok!(test_benchmark_unlist::<Test>());
+            assert_ok!(test_benchmark_buy::<Test>());
+            assert_ok!(test_benchmark_start_auction::<Test>());
+            assert_ok!(test_benchmark_bid::<Test>());
+            assert_ok!(test_benchmark_start_dutch_auction::<Test>());
+            assert_ok!(test_benchmark_cancel_dutch_auction::<Test>());
+            assert_ok!(test_benchmark_buy_dutch::<Test>());
+            assert_ok!(test_benchmark_breed::<Test>());
+            assert_ok!(test_benchmark_breed_many::<Test>());
+            assert_ok!(test_benchmark_offer_sire::<Test>());
+            assert_ok!(test_benchmark_withdraw_sire_offer::<Test>());
+            assert_ok!(test_benchmark_breed_with_sire::<Test>());
+            assert_ok!(test_benchmark_offer_rental::<Test>());
+            assert_ok!(test_benchmark_cancel_rental_offer::<Test>());
+            assert_ok!(test_benchmark_rent::<Test>());
+            assert_ok!(test_benchmark_stake::<Test>());
+            assert_ok!(test_benchmark_unstake::<Test>());
+            assert_ok!(test_benchmark_claim_rewards::<Test>());
+            assert_ok!(test_benchmark_reclaim_metadata_deposit::<Test>());
+            assert_ok!(test_benchmark_fund_rewards::<Test>());
+            assert_ok!(test_benchmark_return_rentals::<Test>());
+            assert_ok!(test_benchmark_on_finalize::<Test>());
+        });
+    }
+}
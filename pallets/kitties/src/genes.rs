@@ -0,0 +1,211 @@
+// 基因：DNA 的字节和kitty 外观特征的对应关系
+//
+// 每个特征占两个字节，前一个是显性基因，后一个是隐性基因，kitty 只表现显性基因。
+// 基因字节的高 4 位是显性等级，低位决定具体的特征值。
+//
+//   [0, 1]  体型
+//   [2, 3]  花纹
+//   [4, 5]  眼睛颜色
+//   [6, 7]  稀有度
+//   [8, 16) 暂时没有对应特征，繁殖时按位随机组合
+use codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use frame_support::RuntimeDebug;
+use sp_runtime::Permill;
+use sp_std::prelude::*;
+
+pub type Dna = [u8; 16];
+
+// 体型
+pub const BODY: usize = 0;
+// 花纹
+pub const PATTERN: usize = 2;
+// 眼睛颜色
+pub const EYE_COLOUR: usize = 4;
+// 稀有度
+pub const RARITY: usize = 6;
+// 有特征的基因占用的字节数
+pub const TRAIT_GENES: usize = 8;
+// 所有特征的位置
+const TRAITS: [usize; 4] = [BODY, PATTERN, EYE_COLOUR, RARITY];
+
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Body {
+    Persian,
+    Siamese,
+    Sphynx,
+    Ragdoll,
+    MaineCoon,
+    Bengal,
+}
+
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum Pattern {
+    Solid,
+    Tabby,
+    Spotted,
+    Calico,
+    Tortoiseshell,
+}
+
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum EyeColour {
+    Blue,
+    Green,
+    Amber,
+    Copper,
+    Odd,
+}
+
+// 稀有度从低到高
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum RarityTier {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+    Legendary,
+}
+
+// 从 DNA 解码出来的特征
+#[derive(Encode, Decode, Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct KittyTraits {
+    pub body: Body,
+    pub pattern: Pattern,
+    pub eye_colour: EyeColour,
+    pub rarity: RarityTier,
+}
+
+impl Body {
+    fn from_gene(gene: u8) -> Self {
+        match gene % 6 {
+            0 => Body::Persian,
+            1 => Body::Siamese,
+            2 => Body::Sphynx,
+            3 => Body::Ragdoll,
+            4 => Body::MaineCoon,
+            _ => Body::Bengal,
+        }
+    }
+}
+
+impl Pattern {
+    fn from_gene(gene: u8) -> Self {
+        match gene % 5 {
+            0 => Pattern::Solid,
+            1 => Pattern::Tabby,
+            2 => Pattern::Spotted,
+            3 => Pattern::Calico,
+            _ => Pattern::Tortoiseshell,
+        }
+    }
+}
+
+impl EyeColour {
+    fn from_gene(gene: u8) -> Self {
+        match gene % 5 {
+            0 => EyeColour::Blue,
+            1 => EyeColour::Green,
+            2 => EyeColour::Amber,
+            3 => EyeColour::Copper,
+            _ => EyeColour::Odd,
+        }
+    }
+}
+
+impl RarityTier {
+    // 稀有度不取模，按区间划分，越稀有的区间越小
+    fn from_gene(gene: u8) -> Self {
+        match gene {
+            0..=127 => RarityTier::Common,
+            128..=191 => RarityTier::Uncommon,
+            192..=239 => RarityTier::Rare,
+            240..=253 => RarityTier::Epic,
+            _ => RarityTier::Legendary,
+        }
+    }
+
+    // 质押奖励的倍数，每高一级翻倍
+    pub fn reward_multiplier(&self) -> u32 {
+        1 << (*self as u32)
+    }
+}
+
+// 解码 DNA 的显性基因
+pub fn decode(dna: &Dna) -> KittyTraits {
+    KittyTraits {
+        body: Body::from_gene(dna[BODY]),
+        pattern: Pattern::from_gene(dna[PATTERN]),
+        eye_colour: EyeColour::from_gene(dna[EYE_COLOUR]),
+        rarity: RarityTier::from_gene(dna[RARITY]),
+    }
+}
+
+// offset 位置的基因表现出来的特征，用序号表示
+pub fn gene_trait(offset: usize, gene: u8) -> u8 {
+    match offset {
+        BODY => Body::from_gene(gene) as u8,
+        PATTERN => Pattern::from_gene(gene) as u8,
+        EYE_COLOUR => EyeColour::from_gene(gene) as u8,
+        _ => RarityTier::from_gene(gene) as u8,
+    }
+}
+
+// 显性等级，高 4 位
+fn dominance(gene: u8) -> u8 {
+    gene >> 4
+}
+
+// 父母各自遗传一个基因，大概率 (3/4) 是显性基因，否则是隐性基因
+fn inherit(dna: &Dna, offset: usize, random: u8) -> u8 {
+    if random % 4 == 0 { dna[offset + 1] } else { dna[offset] }
+}
+
+// 繁殖：每个特征从父母各取一个基因，显性等级高的成为显性基因，相同时父方 (dna1) 优先
+// 没有特征的字节按 random 的位从父母中选择
+pub fn breed(dna1: &Dna, dna2: &Dna, random: &Dna) -> Dna {
+    let mut child = [0u8; 16];
+    for offset in TRAITS.iter().copied() {
+        let gene1 = inherit(dna1, offset, random[offset]);
+        let gene2 = inherit(dna2, offset, random[offset + 1]);
+        let (dominant, recessive) = if dominance(gene2) > dominance(gene1) {
+            (gene2, gene1)
+        } else {
+            (gene1, gene2)
+        };
+        child[offset] = dominant;
+        child[offset + 1] = recessive;
+    }
+    for i in TRAIT_GENES..child.len() {
+        child[i] = (random[i] & dna1[i]) | (!random[i] & dna2[i]);
+    }
+    child
+}
+
+// 突变：每个特征的显性基因有 rate 的概率变成父母双方四个基因都不表现的特征，返回突变的基因位置
+// random 每 4 个字节对应一个特征，前 3 个字节决定是否突变，最后一个字节是突变后的基因
+pub fn mutate(child: &mut Dna, dna1: &Dna, dna2: &Dna, random: &Dna, rate: Permill) -> Vec<u8> {
+    let mut mutations = Vec::new();
+    for (i, offset) in TRAITS.iter().copied().enumerate() {
+        let roll = u32::from_le_bytes([random[4 * i], random[4 * i + 1], random[4 * i + 2], 0]);
+        // roll 在 [0, 2^24) 之间均匀分布
+        if (roll as u64) * 1_000_000 >= (rate.deconstruct() as u64) << 24 {
+            continue;
+        }
+        let parents = [dna1[offset], dna1[offset + 1], dna2[offset], dna2[offset + 1]];
+        // 每个特征最多 6 种，父母只有 4 个基因，总能找到新的特征
+        let mut gene = random[4 * i + 3];
+        while parents.iter().any(|parent| gene_trait(offset, *parent) == gene_trait(offset, gene)) {
+            gene = gene.wrapping_add(1);
+        }
+        child[offset] = gene;
+        mutations.push(offset as u8);
+    }
+    mutations
+}
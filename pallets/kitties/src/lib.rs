@@ -1,62 +1,322 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use codec::{Encode, Decode};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
 use frame_support::{
-    Parameter, RuntimeDebug, StorageDoubleMap, StorageValue, 
+    Parameter, RuntimeDebug, StorageDoubleMap, StorageValue, IterableStorageDoubleMap,
     decl_error, decl_event, decl_module, decl_storage,  
     dispatch::{ DispatchError, DispatchResult }, ensure, 
     traits::Get,
-    traits::{ Currency, ExistenceRequirement::AllowDeath, ReservableCurrency, Randomness },
+    weights::Weight,
+    traits::{ Currency, EnsureOrigin, ExistenceRequirement::{AllowDeath, KeepAlive}, ReservableCurrency, Randomness },
+    transactional,
 };
 use sp_io::hashing::{blake2_128};
 use frame_system::{self as system, ensure_signed};
-use sp_runtime::traits::{AtLeast32BitUnsigned, Bounded, One, CheckedAdd};
+use sp_runtime::{ModuleId, Permill};
+use sp_runtime::traits::{AccountIdConversion, AtLeast32BitUnsigned, Bounded, One, CheckedAdd, Saturating, SaturatedConversion, Zero};
 use sp_std::prelude::*;
+pub use weights::WeightInfo;
+pub use genes::KittyTraits;
 
-// #[cfg(test)]
-// mod mock;
+pub mod genes;
+mod migration;
+pub mod weights;
 
-// #[cfg(test)]
-// mod tests;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 
-#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
-pub struct Kitty(pub [u8; 16]);
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+// 检查近亲最多的祖先代数，每多一代读取的数据翻倍，InbreedingDepth 超过这个值按这个值处理
+pub const MAX_INBREEDING_DEPTH: u32 = 4;
+
+// 存储版本，用于 on_runtime_upgrade 判断是否需要迁移
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+    // Kitty 只有 16 字节 DNA
+    V1_0_0,
+    // Kitty 带有代数、出生区块和冷却区块
+    V2_0_0,
+    // kitty 关系从 KittyNodeStorage 拆成 KittyParents 和 KittyChildren
+    V3_0_0,
+    // AccountKitties 换成 OwnedKitties 索引
+    V4_0_0,
+}
+
+impl Default for Releases {
+    fn default() -> Self {
+        Releases::V1_0_0
+    }
+}
 
-// 感觉这个结构存储的数据有点多，不是很高效
 #[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
-pub struct KittyNode<T: Trait> {
-    // 自己
-    _self: T::KittyIndex,
-    // 父母双方，create的kitty可能是None
-    companion: Option<(T::KittyIndex, T::KittyIndex)>,
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Kitty<BlockNumber> {
+    // kitty 的 DNA
+    pub dna: [u8; 16],
+    // 代数，create 的kitty是0，繁殖出来的是父母中较大的代数 + 1
+    pub generation: u32,
+    // 出生的区块
+    pub birth: BlockNumber,
+    // 冷却结束的区块，在这之前不能繁殖，代数越高冷却越久
+    pub cooldown_until: BlockNumber,
+}
+
+// 需要质押的操作
+#[derive(Clone, Copy, RuntimeDebug, PartialEq, Eq)]
+pub enum DepositAction {
+    Create,
+    Breed,
+}
+
+// 根据操作、kitty 的代数和拥有者已有的kitty 数量计算质押
+pub trait DepositCalculator<Balance> {
+    fn deposit(action: DepositAction, generation: u32, owned: u32) -> Balance;
+}
+
+// 固定质押，所有操作都质押 Amount
+pub struct FixedDeposit<Amount>(sp_std::marker::PhantomData<Amount>);
+
+impl<Balance, Amount: Get<Balance>> DepositCalculator<Balance> for FixedDeposit<Amount> {
+    fn deposit(_action: DepositAction, _generation: u32, _owned: u32) -> Balance {
+        Amount::get()
+    }
+}
+
+// 递增质押：Base + PerGeneration * generation + PerOwned * owned
+pub struct ProgressiveDeposit<Base, PerGeneration, PerOwned>(sp_std::marker::PhantomData<(Base, PerGeneration, PerOwned)>);
+
+impl<Balance, Base, PerGeneration, PerOwned> DepositCalculator<Balance> for ProgressiveDeposit<Base, PerGeneration, PerOwned> where
+    Balance: AtLeast32BitUnsigned,
+    Base: Get<Balance>,
+    PerGeneration: Get<Balance>,
+    PerOwned: Get<Balance>,
+{
+    fn deposit(_action: DepositAction, generation: u32, owned: u32) -> Balance {
+        Base::get()
+            .saturating_add(PerGeneration::get().saturating_mul(generation.into()))
+            .saturating_add(PerOwned::get().saturating_mul(owned.into()))
+    }
+}
+
+// kitty 的名字、简介和图片 URI，长度有上限
+#[derive(Encode, Decode, Clone, Default, RuntimeDebug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct KittyMetadata {
+    pub name: Vec<u8>,
+    pub description: Vec<u8>,
+    // 链下图片的 URI
+    pub uri: Vec<u8>,
+}
+
+impl KittyMetadata {
+    // 总字节数，按这个计算质押
+    pub fn len(&self) -> usize {
+        self.name.len() + self.description.len() + self.uri.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// kitty 的父母和children，给 runtime api 查询用
+#[derive(Encode, Decode, Clone, Default, RuntimeDebug, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct KittyLineage<KittyIndex> {
+    // 父母双方，create的kitty是None
+    pub parents: Option<(KittyIndex, KittyIndex)>,
     // kitty的children
-    children: Vec<T::KittyIndex>,
+    pub children: Vec<KittyIndex>,
+    // kitty 是否已经 burn 掉了
+    pub deceased: bool,
+}
+
+// 英式拍卖，价高者得，到期在 on_finalize 里结算
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct Auction<AccountId, Balance, BlockNumber> {
+    // 卖家
+    pub seller: AccountId,
+    // 保留价，出价不能低于这个价格
+    pub reserve_price: Balance,
+    // 拍卖结束的区块
+    pub end: BlockNumber,
+    // 当前最高出价者和出价，出价的token会被质押
+    pub highest_bid: Option<(AccountId, Balance)>,
+}
+
+// 荷兰式拍卖，价格从 start_price 按区块线性降到 end_price，第一个出价的人按当前价格成交
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct DutchAuction<AccountId, Balance, BlockNumber> {
+    // 卖家
+    pub seller: AccountId,
+    // 起拍价
+    pub start_price: Balance,
+    // 最低价，到达 end 区块之后保持这个价格
+    pub end_price: Balance,
+    // 开始降价的区块
+    pub start: BlockNumber,
+    // 降到最低价的区块
+    pub end: BlockNumber,
+}
+
+// 种猫配种的报价
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct SireOffer<Balance, BlockNumber> {
+    // 配种费用，付给种猫的拥有者
+    pub fee: Balance,
+    // 报价过期的区块
+    pub expires: BlockNumber,
+}
+
+// 出租kitty 的报价
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct RentalOffer<Balance, BlockNumber> {
+    // 租金，付给拥有者
+    pub price: Balance,
+    // 租借的区块数
+    pub duration: BlockNumber,
+}
+
+// 正在出租的kitty，租借期间由 borrower 保管，只能繁殖，不能转移或者出售
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct Rental<AccountId, BlockNumber> {
+    // 租借者
+    pub borrower: AccountId,
+    // 归还的区块，在 on_initialize 里自动归还
+    pub end: BlockNumber,
+}
+
+// 质押kitty 的账户的奖励，按区块懒结算
+#[derive(Encode, Decode, Clone, Default, RuntimeDebug, PartialEq, Eq)]
+pub struct StakingInfo<Balance, BlockNumber> {
+    // 所有质押中的kitty 每个区块的奖励之和
+    pub reward_rate: Balance,
+    // 上次结算之后还没领取的奖励
+    pub pending: Balance,
+    // 上次结算的区块
+    pub last_update: BlockNumber,
 }
 
+type KittyOf<T> = Kitty<<T as system::Trait>::BlockNumber>;
 type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+type AuctionOf<T> = Auction<<T as system::Trait>::AccountId, BalanceOf<T>, <T as system::Trait>::BlockNumber>;
+type SireOfferOf<T> = SireOffer<BalanceOf<T>, <T as system::Trait>::BlockNumber>;
+type RentalOfferOf<T> = RentalOffer<BalanceOf<T>, <T as system::Trait>::BlockNumber>;
+type RentalOf<T> = Rental<<T as system::Trait>::AccountId, <T as system::Trait>::BlockNumber>;
+type DutchAuctionOf<T> = DutchAuction<<T as system::Trait>::AccountId, BalanceOf<T>, <T as system::Trait>::BlockNumber>;
+type StakingInfoOf<T> = StakingInfo<BalanceOf<T>, <T as system::Trait>::BlockNumber>;
 
 pub trait Trait: frame_system::Trait {
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 	type Randomness: Randomness<Self::Hash>;
     type KittyIndex: Parameter + AtLeast32BitUnsigned + Bounded + Default + Copy;
     type Currency: Currency<Self::AccountId> + ReservableCurrency<Self::AccountId>;
-    type KittyReserveFunds: Get<BalanceOf<Self>>;
+    // 每个账户最多拥有的kitty 数量
+    type MaxKittiesPerAccount: Get<u32>;
+    // 计算 create 和 breed 需要质押的数量
+    type DepositCalculator: DepositCalculator<BalanceOf<Self>>;
+    // 繁殖后的基础冷却区块数，实际冷却时间 = BreedCooldown * (generation + 1)
+    type BreedCooldown: Get<Self::BlockNumber>;
+    // 名字、简介和 URI 的最大字节数
+    type MaxNameLength: Get<u32>;
+    type MaxDescriptionLength: Get<u32>;
+    type MaxUriLength: Get<u32>;
+    // metadata 每个字节需要质押的数量
+    type MetadataDepositPerByte: Get<BalanceOf<Self>>;
+    // 转移kitty 时是否清除 metadata，不清除的话质押转由新的拥有者承担
+    type ClearMetadataOnTransfer: Get<bool>;
+    // create_many、transfer_many、breed_many 一次最多处理的数量
+    type MaxBatchSize: Get<u32>;
+    // 同一个区块最多结束的拍卖数量，限制 on_finalize 的工作量
+    type MaxAuctionsPerBlock: Get<u32>;
+    // 同一个区块最多到期的租借数量，限制 on_initialize 的工作量
+    type MaxRentalsPerBlock: Get<u32>;
+    // 检查近亲的祖先代数，至少是 1 (父母、子女、兄弟姐妹)，最多 MAX_INBREEDING_DEPTH，每多一代读取的数据翻倍
+    type InbreedingDepth: Get<u32>;
+    // 繁殖时每个特征发生突变的概率
+    type MutationRate: Get<Permill>;
+    // 奖励池账户的 id，奖励从这个账户支付，资金由 FundOrigin 通过 fund_rewards 增发
+    type ModuleId: Get<ModuleId>;
+    // 质押一只普通kitty 每个区块的奖励，越稀有的kitty 奖励越多
+    type StakingRewardPerBlock: Get<BalanceOf<Self>>;
+    // 可以罚没kitty 质押的 origin
+    type SlashOrigin: EnsureOrigin<Self::Origin>;
+    // 可以向奖励池增发资金的 origin
+    type FundOrigin: EnsureOrigin<Self::Origin>;
+    // 每个交易的权重
+    type WeightInfo: WeightInfo;
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Kitties {
 		// kitty账户 kitty id映射
-        pub Kitties get(fn kitties): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::KittyIndex => Option<Kitty>;
+        pub Kitties get(fn kitties): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::KittyIndex => Option<KittyOf<T>>;
         // Kitty 总数
         pub KittiesCount get(fn kitties_count): T::KittyIndex;
         // Kitty拥有者
         pub KittyOwners get(fn kitty_owner): map hasher(blake2_128_concat) T::KittyIndex => Option<T::AccountId>;
-        // 某账户所有的Kitty
-        pub AccountKitties get(fn account_kitties): map hasher(blake2_128_concat) T::AccountId => Vec<(T::KittyIndex, Kitty)>;
+        // 某账户所有的Kitty (owner, position) => kitty id
+        pub OwnedKitties get(fn owned_kitty): double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) u32 => Option<T::KittyIndex>;
+        // 某账户拥有的Kitty 数量
+        pub OwnedKittiesCount get(fn owned_kitties_count): map hasher(blake2_128_concat) T::AccountId => u32;
+        // kitty 在拥有者 OwnedKitties 里的位置
+        pub OwnedKittiesIndex get(fn owned_kitty_index): map hasher(blake2_128_concat) T::KittyIndex => u32;
         // kitty 对应的质押数量
         pub KittyLockAmount get(fn lock_amount): map hasher(blake2_128_concat) T::KittyIndex => Option<BalanceOf<T>>;
-        // kitty 对应关系
-        pub KittyNodeStorage get(fn get_kitty_from_node): Vec<KittyNode<T>>;
+        // kitty 的父母，create的kitty没有父母
+        pub KittyParents get(fn kitty_parents): map hasher(blake2_128_concat) T::KittyIndex => Option<(T::KittyIndex, T::KittyIndex)>;
+        // kitty 的children (parent, child)
+        pub KittyChildren get(fn kitty_child): double_map hasher(blake2_128_concat) T::KittyIndex, hasher(blake2_128_concat) T::KittyIndex => ();
+        // 已经 burn 掉的kitty，关系里还会引用它
+        pub DeceasedKitties get(fn is_deceased): map hasher(blake2_128_concat) T::KittyIndex => bool;
+        // 挂单出售的kitty 对应的价格
+        pub KittyPrices get(fn kitty_price): map hasher(blake2_128_concat) T::KittyIndex => Option<BalanceOf<T>>;
+        // kitty 最近一次被买走的区块，再次转移之后清除
+        pub KittyBoughtAt get(fn bought_at): map hasher(blake2_128_concat) T::KittyIndex => Option<T::BlockNumber>;
+        // 正在拍卖的kitty
+        pub Auctions get(fn auction): map hasher(blake2_128_concat) T::KittyIndex => Option<AuctionOf<T>>;
+        // 某个区块结束的拍卖
+        pub AuctionsEndAt get(fn auctions_end_at): map hasher(twox_64_concat) T::BlockNumber => Vec<T::KittyIndex>;
+        // 正在荷兰式拍卖的kitty
+        pub DutchAuctions get(fn dutch_auction): map hasher(blake2_128_concat) T::KittyIndex => Option<DutchAuctionOf<T>>;
+        // 提供配种的kitty 对应的报价
+        pub SireOffers get(fn sire_offer): map hasher(blake2_128_concat) T::KittyIndex => Option<SireOfferOf<T>>;
+        // 出租kitty 的报价
+        pub RentalOffers get(fn rental_offer): map hasher(blake2_128_concat) T::KittyIndex => Option<RentalOfferOf<T>>;
+        // 正在出租的kitty
+        pub Rentals get(fn rental): map hasher(blake2_128_concat) T::KittyIndex => Option<RentalOf<T>>;
+        // 某个区块归还的kitty
+        pub RentalsEndAt get(fn rentals_end_at): map hasher(twox_64_concat) T::BlockNumber => Vec<T::KittyIndex>;
+        // kitty 的 metadata
+        pub Metadata get(fn kitty_metadata): map hasher(blake2_128_concat) T::KittyIndex => Option<KittyMetadata>;
+        // metadata 的质押 (设置的账户, 数量)
+        pub MetadataDeposits get(fn metadata_deposit): map hasher(blake2_128_concat) T::KittyIndex => Option<(T::AccountId, BalanceOf<T>)>;
+        // 单只kitty 授权可以转移它的账户
+        pub KittyApprovals get(fn approved): map hasher(blake2_128_concat) T::KittyIndex => Option<T::AccountId>;
+        // 授权可以转移某账户所有kitty 的操作者 (owner, operator)
+        pub OperatorApprovals get(fn is_approved_for_all): double_map hasher(blake2_128_concat) T::AccountId, hasher(blake2_128_concat) T::AccountId => bool;
+        // 质押中的kitty 对应每个区块的奖励
+        pub StakedKitties get(fn staked_kitty): map hasher(blake2_128_concat) T::KittyIndex => Option<BalanceOf<T>>;
+        // 账户的质押奖励
+        pub Stakers get(fn staker): map hasher(blake2_128_concat) T::AccountId => StakingInfoOf<T>;
+        // 存储版本，新链直接是最新版本
+        pub StorageVersion get(fn storage_version) build(|_: &GenesisConfig<T>| Releases::V4_0_0): Releases;
+	}
+	add_extra_genesis {
+		// 创世时的kitty (owner, dna, parents)，kitty id 从 1 开始按顺序分配，父母必须排在前面
+		config(kitties): Vec<(T::AccountId, [u8; 16], Option<(T::KittyIndex, T::KittyIndex)>)>;
+		build(|config: &GenesisConfig<T>| {
+			for (owner, dna, parents) in config.kitties.iter() {
+				Module::<T>::mint_genesis_kitty(owner, *dna, *parents);
+			}
+		});
 	}
 }
 
@@ -70,11 +330,67 @@ decl_event! {
 		/// A kitty is created. \[owner, kitty_id, kitty\]
         Created(AccountId, KittyIndex),
         Transfered(AccountId, AccountId, KittyIndex),
+        /// A kitty is burned and its deposit refunded. \[owner, kitty_id\]
+        Burned(AccountId, KittyIndex),
+        /// A kitty is listed for sale. \[owner, kitty_id, price\]
+        Listed(AccountId, KittyIndex, Balance),
+        /// A kitty is removed from sale. \[owner, kitty_id\]
+        Unlisted(AccountId, KittyIndex),
+        /// A listed kitty is bought. \[seller, buyer, kitty_id, price\]
+        Sold(AccountId, AccountId, KittyIndex, Balance),
+        /// An auction is started. \[seller, kitty_id, reserve_price, end\]
+        AuctionStarted(AccountId, KittyIndex, Balance, BlockNumber),
+        /// A new highest bid is placed. \[bidder, kitty_id, amount\]
+        BidPlaced(AccountId, KittyIndex, Balance),
+        /// An auction is settled with a winner. \[seller, winner, kitty_id, price\]
+        AuctionSettled(AccountId, AccountId, KittyIndex, Balance),
+        /// An auction ended without a valid bid, the kitty stays with the seller. \[seller, kitty_id\]
+        AuctionExpired(AccountId, KittyIndex),
+        /// A dutch auction is started. \[seller, kitty_id, start_price, end_price, end\]
+        DutchAuctionStarted(AccountId, KittyIndex, Balance, Balance, BlockNumber),
+        /// A kitty in dutch auction is bought at the current price. \[seller, buyer, kitty_id, price\]
+        DutchAuctionSold(AccountId, AccountId, KittyIndex, Balance),
+        /// A dutch auction is cancelled by the seller. \[seller, kitty_id\]
+        DutchAuctionCancelled(AccountId, KittyIndex),
+        /// A kitty is offered as a sire. \[owner, kitty_id, fee, expires\]
+        SireOffered(AccountId, KittyIndex, Balance, BlockNumber),
+        /// A sire offer is withdrawn. \[owner, kitty_id\]
+        SireOfferWithdrawn(AccountId, KittyIndex),
+        /// A kitty is bred with someone else's sire. \[requester, sire_owner, sire_id, child_id, fee\]
+        Sired(AccountId, AccountId, KittyIndex, KittyIndex, Balance),
+        /// A kitty is born from breeding, with the positions of mutated genes. \[owner, kitty_id, mutations\]
+        Born(AccountId, KittyIndex, Vec<u8>),
+        /// A kitty is offered for rent. \[owner, kitty_id, price, duration\]
+        RentalOffered(AccountId, KittyIndex, Balance, BlockNumber),
+        /// A rental offer is cancelled. \[owner, kitty_id\]
+        RentalOfferCancelled(AccountId, KittyIndex),
+        /// A kitty is rented. \[owner, borrower, kitty_id, price, end\]
+        Rented(AccountId, AccountId, KittyIndex, Balance, BlockNumber),
+        /// A rented kitty is returned to its owner. \[owner, borrower, kitty_id\]
+        RentalEnded(AccountId, AccountId, KittyIndex),
+        /// The metadata of a kitty is set. \[owner, kitty_id, deposit\]
+        MetadataSet(AccountId, KittyIndex, Balance),
+        /// The metadata of a kitty is cleared and its deposit refunded. \[depositor, kitty_id\]
+        MetadataCleared(AccountId, KittyIndex),
+        /// An account is approved to transfer a kitty. \[owner, operator, kitty_id\]
+        Approval(AccountId, AccountId, KittyIndex),
+        /// An operator is approved or revoked for all kitties of an owner. \[owner, operator, approved\]
+        ApprovalForAll(AccountId, AccountId, bool),
+        /// A kitty is staked. \[owner, kitty_id, reward_per_block\]
+        Staked(AccountId, KittyIndex, Balance),
+        /// A kitty is unstaked. \[owner, kitty_id\]
+        Unstaked(AccountId, KittyIndex),
+        /// Staking rewards are paid out. \[owner, amount\]
+        RewardsClaimed(AccountId, Balance),
+        /// The staking reward pool is topped up with newly issued funds. \[amount\]
+        RewardPoolFunded(Balance),
 
         LockFunds(AccountId, Balance, BlockNumber),
 		UnlockFunds(AccountId, Balance, BlockNumber),
 		// sender, dest, amount, block number
         TransferFunds(AccountId, AccountId, Balance, BlockNumber),
+        /// The deposit of a kitty is slashed. \[owner, dest, kitty_id, amount\]
+        DepositSlashed(AccountId, AccountId, KittyIndex, Balance),
 	}
 }
 
@@ -86,6 +402,60 @@ decl_error! {
         AccountNotExist,
 
         BalanceNotEnough,
+        // 不能购买自己的kitty
+        BuyOwnKitty,
+        // kitty 没有挂单出售
+        KittyNotForSale,
+        // kitty 正在拍卖中
+        KittyInAuction,
+        // 拍卖不存在
+        AuctionNotExist,
+        // 拍卖已结束
+        AuctionAlreadyEnded,
+        // 拍卖结束区块必须大于当前区块
+        InvalidAuctionEnd,
+        // 出价低于保留价或当前最高价
+        BidTooLow,
+        // 荷兰式拍卖的起拍价不能低于最低价
+        InvalidAuctionPrice,
+        // 这只kitty 没有提供配种
+        SireOfferNotExist,
+        // 配种报价已过期
+        SireOfferExpired,
+        // kitty 还在冷却中，不能繁殖
+        KittyInCooldown,
+        // 账户拥有的kitty 数量达到上限
+        TooManyKitties,
+        // 近亲不能繁殖
+        InbreedingNotAllowed,
+        // 名字、简介或 URI 太长
+        MetadataTooLong,
+        // kitty 正在出租中
+        KittyRented,
+        // 这只kitty 没有出租
+        RentalOfferNotExist,
+        // 租借的区块数不能是0
+        InvalidRentalDuration,
+        // 批量操作的数量超过上限
+        BatchTooLarge,
+        // 没有被授权转移这只kitty
+        NotApproved,
+        // kitty 正在质押中
+        KittyStaked,
+        // kitty 没有质押
+        KittyNotStaked,
+        // 没有可以领取的奖励
+        NoRewards,
+        // 奖励池余额不足
+        RewardPoolExhausted,
+        // 这个区块结束的拍卖数量达到上限
+        TooManyAuctions,
+        // kitty 没有需要退还的 metadata 质押
+        NoMetadataDeposit,
+        // 这个区块到期的租借数量达到上限
+        TooManyRentals,
+        // 价格超过了买家愿意支付的上限
+        PriceTooHigh,
 	}
 }
 
@@ -95,144 +465,581 @@ decl_module! {
 
         fn deposit_event() = default;
 
-        #[weight = 0]
-		pub fn reserve_funds(origin, locker: T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
-            let _sender = ensure_signed(origin)?;
+        fn on_runtime_upgrade() -> Weight {
+            migration::migrate_to_v2::<T>()
+                .saturating_add(migration::migrate_to_v3::<T>())
+                .saturating_add(migration::migrate_to_v4::<T>())
+        }
+
+        // 罚没kitty 的质押，只有 SlashOrigin (root 或治理) 可以调用
+        #[weight = T::WeightInfo::slash_deposit()]
+        #[transactional]
+        pub fn slash_deposit(origin, kitty_id: T::KittyIndex, dest: T::AccountId) -> DispatchResult {
+            T::SlashOrigin::ensure_origin(origin)?;
+            let owner = Self::kitty_owner(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+            let amount = Self::lock_amount(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+
+            Self::unlock_and_transfer(&owner, &dest, amount)?;
+            KittyLockAmount::<T>::insert(kitty_id, BalanceOf::<T>::zero());
+
+            Self::deposit_event(RawEvent::DepositSlashed(owner, dest, kitty_id, amount));
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::create()]
+        #[transactional]
+        pub fn create(origin) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_create(&sender)?;
+
+            Ok(())
+        }
+
+        // 一次创建 count 只kitty，任何一只失败全部回滚
+        #[weight = T::WeightInfo::create_many(*count)]
+        #[transactional]
+        pub fn create_many(origin, count: u32) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(count <= T::MaxBatchSize::get(), Error::<T>::BatchTooLarge);
+
+            for _ in 0..count {
+                Self::do_create(&sender)?;
+            }
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::transfer()]
+        #[transactional]
+        pub fn transfer(origin, to: T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            Self::do_transfer(&sender, &to, kitty_id)?;
+
+            Self::deposit_event(RawEvent::Transfered(sender, to, kitty_id));
+            Ok(())
+        }
+
+        // 一次转移多只kitty，任何一只失败全部回滚
+        #[weight = T::WeightInfo::transfer_many(transfers.len() as u32)]
+        #[transactional]
+        pub fn transfer_many(origin, transfers: Vec<(T::AccountId, T::KittyIndex)>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(transfers.len() <= T::MaxBatchSize::get() as usize, Error::<T>::BatchTooLarge);
+
+            for (to, kitty_id) in transfers {
+                Self::do_transfer(&sender, &to, kitty_id)?;
+                Self::deposit_event(RawEvent::Transfered(sender.clone(), to, kitty_id));
+            }
+            Ok(())
+        }
+
+        // 授权 operator 转移一只kitty，拥有者或者全部授权的操作者可以调用
+        // 授权给拥有者自己相当于取消授权
+        #[weight = T::WeightInfo::approve()]
+        #[transactional]
+        pub fn approve(origin, kitty_id: T::KittyIndex, operator: T::AccountId) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let owner = Self::kitty_owner(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+            ensure!(sender == owner || Self::is_approved_for_all(&owner, &sender), Error::<T>::NotApproved);
+
+            if operator == owner {
+                KittyApprovals::<T>::remove(kitty_id);
+            } else {
+                KittyApprovals::<T>::insert(kitty_id, &operator);
+            }
+
+            Self::deposit_event(RawEvent::Approval(owner, operator, kitty_id));
+            Ok(())
+        }
+
+        // 授权或者取消授权 operator 转移自己所有的kitty
+        #[weight = T::WeightInfo::set_approval_for_all()]
+        #[transactional]
+        pub fn set_approval_for_all(origin, operator: T::AccountId, approved: bool) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
 
-            // 这里其实已经判断了余额不足的 但是这个Error Event 还没找到怎么发送 BalanceNotEnough
-            T::Currency::reserve(&locker, amount)
+            if approved {
+                OperatorApprovals::<T>::insert(&sender, &operator, true);
+            } else {
+                OperatorApprovals::<T>::remove(&sender, &operator);
+            }
+
+            Self::deposit_event(RawEvent::ApprovalForAll(sender, operator, approved));
+            Ok(())
+        }
+
+        // 拥有者或者被授权的账户把 from 的kitty 转给 to
+        #[weight = T::WeightInfo::transfer_from()]
+        #[transactional]
+        pub fn transfer_from(origin, from: T::AccountId, to: T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(from.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(Self::can_transfer(&sender, &from, kitty_id), Error::<T>::NotApproved);
+
+            Self::do_transfer(&from, &to, kitty_id)?;
+
+            Self::deposit_event(RawEvent::Transfered(from, to, kitty_id));
+            Ok(())
+        }
+
+        // 设置kitty 的名字、简介和图片 URI，按字节数质押，全部为空时清除
+        #[weight = T::WeightInfo::set_metadata((name.len() + description.len() + uri.len()) as u32)]
+        #[transactional]
+        pub fn set_metadata(
+            origin,
+            kitty_id: T::KittyIndex,
+            name: Vec<u8>,
+            description: Vec<u8>,
+            uri: Vec<u8>
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(name.len() <= T::MaxNameLength::get() as usize, Error::<T>::MetadataTooLong);
+            ensure!(description.len() <= T::MaxDescriptionLength::get() as usize, Error::<T>::MetadataTooLong);
+            ensure!(uri.len() <= T::MaxUriLength::get() as usize, Error::<T>::MetadataTooLong);
+
+            // 先退还之前的质押，可能是上一个拥有者设置的
+            Self::clear_metadata(kitty_id);
+            let metadata = KittyMetadata { name, description, uri };
+            if metadata.is_empty() {
+                return Ok(());
+            }
+
+            let deposit = T::MetadataDepositPerByte::get().saturating_mul((metadata.len() as u32).into());
+            T::Currency::reserve(&sender, deposit)
                 .map_err(|_| Error::<T>::BalanceNotEnough)?;
+            Metadata::<T>::insert(kitty_id, metadata);
+            MetadataDeposits::<T>::insert(kitty_id, (sender.clone(), deposit));
+
+            Self::deposit_event(RawEvent::MetadataSet(sender, kitty_id, deposit));
+            Ok(())
+        }
 
+        // 放弃kitty，退还质押。kitty 关系保留，标记为已死亡
+        #[weight = T::WeightInfo::burn()]
+        #[transactional]
+        pub fn burn(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+            ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+            ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+            Kitties::<T>::take(&sender, kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+
+            KittyOwners::<T>::remove(kitty_id);
+            Self::remove_owned_kitty(&sender, kitty_id);
+            KittyPrices::<T>::remove(kitty_id);
+            SireOffers::<T>::remove(kitty_id);
+            RentalOffers::<T>::remove(kitty_id);
+            KittyApprovals::<T>::remove(kitty_id);
+            KittyBoughtAt::<T>::remove(kitty_id);
+            Self::clear_metadata(kitty_id);
+            DeceasedKitties::<T>::insert(kitty_id, true);
+
+            // 退还质押
+            let amount = KittyLockAmount::<T>::take(kitty_id).unwrap_or_else(Zero::zero);
+            T::Currency::unreserve(&sender, amount);
             let now = <system::Module<T>>::block_number();
-            Self::deposit_event(RawEvent::LockFunds(locker, amount, now));
-			
-			Ok(())
-		}
-        
-        #[weight = 10_000]
-		pub fn unreserve_and_transfer(
-			origin,
-			to_punish: T::AccountId,
-			dest: T::AccountId,
-			collateral: BalanceOf<T>
-		) -> DispatchResult {
-			let _ = ensure_signed(origin)?; // dangerous because can be called with any signature (so dont do this in practice ever!)
-
-						// If collateral is bigger than to_punish's reserved_balance, store what's left in overdraft.
-			let overdraft = T::Currency::unreserve(&to_punish, collateral);
-
-			T::Currency::transfer(&to_punish, &dest, collateral - overdraft, AllowDeath)?;
-
-			let now = <system::Module<T>>::block_number();
-			Self::deposit_event(RawEvent::TransferFunds(to_punish, dest, collateral - overdraft, now));
-
-			Ok(())
-		}
-
-        #[weight = 1000]
-        pub fn create(origin) -> DispatchResult {
-            let sender = ensure_signed(origin.clone())?;
+            Self::deposit_event(RawEvent::UnlockFunds(sender.clone(), amount, now));
+
+            Self::deposit_event(RawEvent::Burned(sender, kitty_id));
+            Ok(())
+        }
 
-            let kitty_id = Self::next_kitty_id()?;
+        #[weight = T::WeightInfo::list()]
+        #[transactional]
+        pub fn list(origin, kitty_id: T::KittyIndex, price: BalanceOf<T>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+            ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+            ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
 
-            let dna = Self::random_value(&sender);
+            KittyPrices::<T>::insert(kitty_id, price);
 
-            let kitty = Kitty(dna);
+            Self::deposit_event(RawEvent::Listed(sender, kitty_id, price));
+            Ok(())
+        }
 
-            Self::insert_kitty(&sender, kitty_id, kitty)?;
+        #[weight = T::WeightInfo::unlist()]
+        #[transactional]
+        pub fn unlist(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(KittyPrices::<T>::contains_key(kitty_id), Error::<T>::KittyNotForSale);
 
-            let amount = T::KittyReserveFunds::get();
+            KittyPrices::<T>::remove(kitty_id);
 
-            KittyLockAmount::<T>::insert(kitty_id, amount);
+            Self::deposit_event(RawEvent::Unlisted(sender, kitty_id));
+            Ok(())
+        }
 
-            Self::reserve_funds(origin, sender.clone(), amount)?;
+        // 按挂单价格购买，价格超过 max_price 时失败，避免卖家抢先提高价格
+        #[weight = T::WeightInfo::buy()]
+        #[transactional]
+        pub fn buy(origin, kitty_id: T::KittyIndex, max_price: BalanceOf<T>) -> DispatchResult {
+            let buyer = ensure_signed(origin)?;
+            let seller = Self::kitty_owner(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+            ensure!(seller != buyer, Error::<T>::BuyOwnKitty);
+            let price = Self::kitty_price(kitty_id).ok_or(Error::<T>::KittyNotForSale)?;
+            ensure!(price <= max_price, Error::<T>::PriceTooHigh);
 
-            Self::deposit_event(RawEvent::Created(sender.clone(), kitty_id));
+            Self::do_sell(&seller, &buyer, kitty_id, price)?;
 
-            // 更新kitty node
-            let mut node_vec = KittyNodeStorage::<T>::take();
-            let node = KittyNode {
-                _self: kitty_id,
-                children: Vec::new(),
-                companion: None, // 
-            };
-            node_vec.push(node);
-            // 更新kittynode 关系vec
-            KittyNodeStorage::<T>::put(node_vec);
+            Self::deposit_event(RawEvent::Sold(seller, buyer, kitty_id, price));
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::start_auction()]
+        #[transactional]
+        pub fn start_auction(origin, kitty_id: T::KittyIndex, reserve_price: BalanceOf<T>, end: T::BlockNumber) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+            ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+            ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+            let now = <system::Module<T>>::block_number();
+            ensure!(end > now, Error::<T>::InvalidAuctionEnd);
+            let ending = AuctionsEndAt::<T>::decode_len(end).unwrap_or(0);
+            ensure!(ending < T::MaxAuctionsPerBlock::get() as usize, Error::<T>::TooManyAuctions);
 
+            // 拍卖期间不能再按固定价格出售
+            KittyPrices::<T>::remove(kitty_id);
+            Auctions::<T>::insert(kitty_id, Auction {
+                seller: sender.clone(),
+                reserve_price,
+                end,
+                highest_bid: None,
+            });
+            AuctionsEndAt::<T>::append(end, kitty_id);
+
+            Self::deposit_event(RawEvent::AuctionStarted(sender, kitty_id, reserve_price, end));
             Ok(())
         }
 
-        #[weight = 0]
-        pub fn transfer(origin, to: T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
-            let sender = ensure_signed(origin.clone())?;
-            let kitty = Kitties::<T>::take(&sender, kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
-
-            // 查找到需要转移到那只 kitty 变更 kitty的所有者关系
-            let sender_kitty_vec = AccountKitties::<T>::take(&sender);
-            let mut to_kitty_vec = AccountKitties::<T>::take(&to);
-            let mut new_sender_k_vec = Vec::new();
-            for (kid, kt) in sender_kitty_vec.iter() {
-                if kid != &kitty_id {
-                    new_sender_k_vec.push((*kid, kt));
-                } else {
-                    to_kitty_vec.push((*kid, kitty.clone()));
-                }
+        #[weight = T::WeightInfo::bid()]
+        #[transactional]
+        pub fn bid(origin, kitty_id: T::KittyIndex, amount: BalanceOf<T>) -> DispatchResult {
+            let bidder = ensure_signed(origin)?;
+            let mut auction = Self::auction(kitty_id).ok_or(Error::<T>::AuctionNotExist)?;
+            ensure!(auction.seller != bidder, Error::<T>::BuyOwnKitty);
+            let now = <system::Module<T>>::block_number();
+            ensure!(now < auction.end, Error::<T>::AuctionAlreadyEnded);
+            ensure!(amount >= auction.reserve_price, Error::<T>::BidTooLow);
+            if let Some((_, highest)) = &auction.highest_bid {
+                ensure!(amount > *highest, Error::<T>::BidTooLow);
             }
-            AccountKitties::<T>::insert(&sender, new_sender_k_vec);
-            AccountKitties::<T>::insert(&to, to_kitty_vec);
-            KittyOwners::<T>::insert(&kitty_id, to.clone());
 
-            // 获取kitty的质押数量
-            let amount = Self::lock_amount(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
-            // 解除质押，并转移质押到拥有者账号
-            Self::unreserve_and_transfer(origin.clone(), sender.clone(), to.clone(), amount)?;
-            // 把质押的token 质押到拥有者账号里 (会不会产生在上面解除质押，转移的过程中，toke还没到账，然后账户上没有足够的token去质押的情况呢？也就是，这里是同步的，不是异步执行的吧)
-            Self::reserve_funds(origin, to.clone(), amount)?;
+            // 质押新的出价，然后退还上一个出价者的质押
+            T::Currency::reserve(&bidder, amount)
+                .map_err(|_| Error::<T>::BalanceNotEnough)?;
+            if let Some((prev_bidder, prev_amount)) = auction.highest_bid.take() {
+                T::Currency::unreserve(&prev_bidder, prev_amount);
+            }
+            auction.highest_bid = Some((bidder.clone(), amount));
+            Auctions::<T>::insert(kitty_id, auction);
 
-            Self::deposit_event(RawEvent::Transfered(sender, to, kitty_id));
+            Self::deposit_event(RawEvent::BidPlaced(bidder, kitty_id, amount));
             Ok(())
         }
 
-        #[weight = 0]
-        pub fn breed(origin, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) {
-            let sender = ensure_signed(origin.clone())?;
-            let amount = T::KittyReserveFunds::get();
-
-            let new_kitty_id = Self::do_breed(&sender, kitty_id_1, kitty_id_2)?;
-            // 质押token
-            KittyLockAmount::<T>::insert(&new_kitty_id, amount.clone());
-
-            Self::reserve_funds(origin, sender.clone(), amount)?;
-
-            // 更新kitty node children关系
-            let mut node_vec = KittyNodeStorage::<T>::take();
-            for k in &mut node_vec.iter_mut() {
-                if k._self == kitty_id_1 {
-                    k.children.push(new_kitty_id);
-                } else if k._self == kitty_id_2 {
-                    k.children.push(new_kitty_id);
+        #[weight = T::WeightInfo::start_dutch_auction()]
+        #[transactional]
+        pub fn start_dutch_auction(
+            origin,
+            kitty_id: T::KittyIndex,
+            start_price: BalanceOf<T>,
+            end_price: BalanceOf<T>,
+            end: T::BlockNumber
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+            ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+            ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+            ensure!(start_price >= end_price, Error::<T>::InvalidAuctionPrice);
+            let now = <system::Module<T>>::block_number();
+            ensure!(end > now, Error::<T>::InvalidAuctionEnd);
+
+            KittyPrices::<T>::remove(kitty_id);
+            DutchAuctions::<T>::insert(kitty_id, DutchAuction {
+                seller: sender.clone(),
+                start_price,
+                end_price,
+                start: now,
+                end,
+            });
+
+            Self::deposit_event(RawEvent::DutchAuctionStarted(sender, kitty_id, start_price, end_price, end));
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::cancel_dutch_auction()]
+        #[transactional]
+        pub fn cancel_dutch_auction(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let auction = Self::dutch_auction(kitty_id).ok_or(Error::<T>::AuctionNotExist)?;
+            ensure!(auction.seller == sender, Error::<T>::InvalidaKittyId);
+
+            DutchAuctions::<T>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::DutchAuctionCancelled(sender, kitty_id));
+            Ok(())
+        }
+
+        // 按当前价格购买，价格超过 max_price 时失败，避免卖家抢先重新开始拍卖
+        #[weight = T::WeightInfo::buy_dutch()]
+        #[transactional]
+        pub fn buy_dutch(origin, kitty_id: T::KittyIndex, max_price: BalanceOf<T>) -> DispatchResult {
+            let buyer = ensure_signed(origin)?;
+            let auction = Self::dutch_auction(kitty_id).ok_or(Error::<T>::AuctionNotExist)?;
+            ensure!(auction.seller != buyer, Error::<T>::BuyOwnKitty);
+
+            let price = Self::dutch_price(&auction, <system::Module<T>>::block_number());
+            ensure!(price <= max_price, Error::<T>::PriceTooHigh);
+            DutchAuctions::<T>::remove(kitty_id);
+            Self::do_sell(&auction.seller, &buyer, kitty_id, price)?;
+
+            Self::deposit_event(RawEvent::DutchAuctionSold(auction.seller, buyer, kitty_id, price));
+            Ok(())
+        }
+
+        fn on_initialize(now: T::BlockNumber) -> Weight {
+            // 到期的kitty 归还给拥有者
+            let returned = Self::return_rentals(now);
+            // 结算拍卖在 on_finalize 里，权重在这里提前算好
+            let ending = AuctionsEndAt::<T>::decode_len(now).unwrap_or(0);
+            T::WeightInfo::on_finalize(ending as u32)
+                .saturating_add(T::WeightInfo::return_rentals(returned))
+        }
+
+        fn on_finalize(now: T::BlockNumber) {
+            for kitty_id in AuctionsEndAt::<T>::take(now) {
+                if let Some(auction) = Auctions::<T>::take(kitty_id) {
+                    Self::settle_auction(kitty_id, auction);
                 }
             }
+        }
 
-            let node = KittyNode {
-                _self: new_kitty_id,
-                children: Vec::new(),
-                companion: Some((kitty_id_1, kitty_id_2)),
-            };
+        #[weight = T::WeightInfo::breed(Module::<T>::inbreeding_depth())]
+        #[transactional]
+        pub fn breed(origin, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) {
+            let sender = ensure_signed(origin)?;
+
+            let new_kitty_id = Self::breed_child(&sender, kitty_id_1, &sender, kitty_id_2)?;
 
-            node_vec.push(node);
-            // 更新kittynode 关系vec
-            KittyNodeStorage::<T>::put(node_vec);
-            
             Self::deposit_event(RawEvent::Created(sender, new_kitty_id));
         }
-	}
-}
 
+        // 一次繁殖多对kitty，任何一对失败全部回滚
+        #[weight = T::WeightInfo::breed_many(pairs.len() as u32, Module::<T>::inbreeding_depth())]
+        #[transactional]
+        pub fn breed_many(origin, pairs: Vec<(T::KittyIndex, T::KittyIndex)>) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(pairs.len() <= T::MaxBatchSize::get() as usize, Error::<T>::BatchTooLarge);
+
+            for (kitty_id_1, kitty_id_2) in pairs {
+                let new_kitty_id = Self::breed_child(&sender, kitty_id_1, &sender, kitty_id_2)?;
+                Self::deposit_event(RawEvent::Created(sender.clone(), new_kitty_id));
+            }
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::offer_sire()]
+        #[transactional]
+        pub fn offer_sire(origin, kitty_id: T::KittyIndex, fee: BalanceOf<T>, expires: T::BlockNumber) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+            ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+            let now = <system::Module<T>>::block_number();
+            ensure!(expires > now, Error::<T>::SireOfferExpired);
+
+            SireOffers::<T>::insert(kitty_id, SireOffer { fee, expires });
+
+            Self::deposit_event(RawEvent::SireOffered(sender, kitty_id, fee, expires));
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::withdraw_sire_offer()]
+        #[transactional]
+        pub fn withdraw_sire_offer(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(SireOffers::<T>::contains_key(kitty_id), Error::<T>::SireOfferNotExist);
+
+            SireOffers::<T>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::SireOfferWithdrawn(sender, kitty_id));
+            Ok(())
+        }
+
+        // 付配种费和种猫繁殖，配种费超过 max_fee 时失败，避免种猫拥有者抢先提高配种费
+        #[weight = T::WeightInfo::breed_with_sire(Module::<T>::inbreeding_depth())]
+        #[transactional]
+        pub fn breed_with_sire(
+            origin,
+            kitty_id: T::KittyIndex,
+            sire_id: T::KittyIndex,
+            max_fee: BalanceOf<T>
+        ) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let offer = Self::sire_offer(sire_id).ok_or(Error::<T>::SireOfferNotExist)?;
+            let now = <system::Module<T>>::block_number();
+            ensure!(now <= offer.expires, Error::<T>::SireOfferExpired);
+            ensure!(offer.fee <= max_fee, Error::<T>::PriceTooHigh);
+            let sire_owner = Self::kitty_owner(sire_id).ok_or(Error::<T>::InvalidaKittyId)?;
+            ensure!(sire_owner != sender, Error::<T>::BuyOwnKitty);
+
+            // 配种费付给种猫的拥有者，小kitty归配种的发起者
+            T::Currency::transfer(&sender, &sire_owner, offer.fee, KeepAlive)
+                .map_err(|_| Error::<T>::BalanceNotEnough)?;
+            let new_kitty_id = Self::breed_child(&sender, kitty_id, &sire_owner, sire_id)?;
+
+            Self::deposit_event(RawEvent::Sired(sender.clone(), sire_owner, sire_id, new_kitty_id, offer.fee));
+            Self::deposit_event(RawEvent::Created(sender, new_kitty_id));
+            Ok(())
+        }
+
+        // 出租kitty，duration 个区块之后自动归还
+        #[weight = T::WeightInfo::offer_rental()]
+        #[transactional]
+        pub fn offer_rental(origin, kitty_id: T::KittyIndex, price: BalanceOf<T>, duration: T::BlockNumber) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+            ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+            ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+            ensure!(!duration.is_zero(), Error::<T>::InvalidRentalDuration);
+
+            RentalOffers::<T>::insert(kitty_id, RentalOffer { price, duration });
+
+            Self::deposit_event(RawEvent::RentalOffered(sender, kitty_id, price, duration));
+            Ok(())
+        }
+
+        #[weight = T::WeightInfo::cancel_rental_offer()]
+        #[transactional]
+        pub fn cancel_rental_offer(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            ensure!(RentalOffers::<T>::contains_key(kitty_id), Error::<T>::RentalOfferNotExist);
+
+            RentalOffers::<T>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::RentalOfferCancelled(sender, kitty_id));
+            Ok(())
+        }
+
+        // 付租金给拥有者，租借期间由 sender 保管kitty。租金超过 max_price 时失败，避免拥有者抢先提高租金
+        #[weight = T::WeightInfo::rent()]
+        #[transactional]
+        pub fn rent(origin, kitty_id: T::KittyIndex, max_price: BalanceOf<T>) -> DispatchResult {
+            let borrower = ensure_signed(origin)?;
+            let offer = Self::rental_offer(kitty_id).ok_or(Error::<T>::RentalOfferNotExist)?;
+            ensure!(offer.price <= max_price, Error::<T>::PriceTooHigh);
+            let owner = Self::kitty_owner(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+            ensure!(owner != borrower, Error::<T>::BuyOwnKitty);
+            ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+            let end = <system::Module<T>>::block_number().saturating_add(offer.duration);
+            let ending = RentalsEndAt::<T>::decode_len(end).unwrap_or(0);
+            ensure!(ending < T::MaxRentalsPerBlock::get() as usize, Error::<T>::TooManyRentals);
+
+            T::Currency::transfer(&borrower, &owner, offer.price, KeepAlive)
+                .map_err(|_| Error::<T>::BalanceNotEnough)?;
+
+            // 出租期间不能出售，也不能配种
+            RentalOffers::<T>::remove(kitty_id);
+            KittyPrices::<T>::remove(kitty_id);
+            SireOffers::<T>::remove(kitty_id);
+            Rentals::<T>::insert(kitty_id, Rental { borrower: borrower.clone(), end });
+            RentalsEndAt::<T>::append(end, kitty_id);
+
+            Self::deposit_event(RawEvent::Rented(owner, borrower, kitty_id, offer.price, end));
+            Ok(())
+        }
+
+        // 质押kitty 获得奖励，质押期间不能转移、出售或者繁殖
+        #[weight = T::WeightInfo::stake()]
+        #[transactional]
+        pub fn stake(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let kitty = Self::kitties(&sender, kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+            ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+            ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+            ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+
+            let reward = Self::staking_reward(&kitty);
+            let mut info = Self::settle_rewards(&sender);
+            info.reward_rate = info.reward_rate.saturating_add(reward);
+            Stakers::<T>::insert(&sender, info);
+            StakedKitties::<T>::insert(kitty_id, reward);
+            KittyPrices::<T>::remove(kitty_id);
+            SireOffers::<T>::remove(kitty_id);
+            RentalOffers::<T>::remove(kitty_id);
+
+            Self::deposit_event(RawEvent::Staked(sender, kitty_id, reward));
+            Ok(())
+        }
+
+        // 取消质押，已经产生的奖励保留，可以之后领取
+        #[weight = T::WeightInfo::unstake()]
+        #[transactional]
+        pub fn unstake(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender.clone()), Error::<T>::InvalidaKittyId);
+            let reward = StakedKitties::<T>::take(kitty_id).ok_or(Error::<T>::KittyNotStaked)?;
 
-fn combine_dna(dna1: u8, dna2: u8, selector: u8) -> u8 {
-    (selector & dna1) | (!selector & dna2)
+            let mut info = Self::settle_rewards(&sender);
+            info.reward_rate = info.reward_rate.saturating_sub(reward);
+            Self::update_staker(&sender, info);
+
+            Self::deposit_event(RawEvent::Unstaked(sender, kitty_id));
+            Ok(())
+        }
+
+        // 从奖励池领取到目前为止的所有奖励
+        #[weight = T::WeightInfo::claim_rewards()]
+        #[transactional]
+        pub fn claim_rewards(origin) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            let mut info = Self::settle_rewards(&sender);
+            let amount = info.pending;
+            ensure!(!amount.is_zero(), Error::<T>::NoRewards);
+
+            T::Currency::transfer(&Self::account_id(), &sender, amount, KeepAlive)
+                .map_err(|_| Error::<T>::RewardPoolExhausted)?;
+            info.pending = Zero::zero();
+            Self::update_staker(&sender, info);
+
+            Self::deposit_event(RawEvent::RewardsClaimed(sender, amount));
+            Ok(())
+        }
+
+        // 拥有者删除kitty 的 metadata，退还质押给设置的账户
+        #[weight = T::WeightInfo::reclaim_metadata_deposit()]
+        #[transactional]
+        pub fn reclaim_metadata_deposit(origin, kitty_id: T::KittyIndex) -> DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::kitty_owner(kitty_id) == Some(sender), Error::<T>::InvalidaKittyId);
+            ensure!(MetadataDeposits::<T>::contains_key(kitty_id), Error::<T>::NoMetadataDeposit);
+
+            Self::clear_metadata(kitty_id);
+            Ok(())
+        }
+
+        // 向奖励池增发资金，只有 FundOrigin (root 或治理) 可以调用
+        #[weight = T::WeightInfo::fund_rewards()]
+        #[transactional]
+        pub fn fund_rewards(origin, amount: BalanceOf<T>) -> DispatchResult {
+            T::FundOrigin::ensure_origin(origin)?;
+
+            let _ = T::Currency::deposit_creating(&Self::account_id(), amount);
+
+            Self::deposit_event(RawEvent::RewardPoolFunded(amount));
+            Ok(())
+        }
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -242,43 +1049,458 @@ impl<T: Trait> Module<T> {
         Ok(kitty_id)
     }
 
-    // 随机数
+    // 随机数，加上 kitty 总数，同一个交易里批量创建的kitty 也不一样
     fn random_value(sender: &T::AccountId) -> [u8;16] {
         let payload = (
             T::Randomness::random_seed(),
             &sender,
             <frame_system::Module<T>>::extrinsic_index(),
+            Self::kitties_count(),
         );
 
         payload.using_encoded(blake2_128)
     }
 
-    fn insert_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex, kitty: Kitty) -> DispatchResult {
+    // 突变用的随机数，和选择父母基因的随机数分开
+    fn mutation_random(sender: &T::AccountId) -> [u8;16] {
+        let payload = (
+            T::Randomness::random(b"kitties/mutation"),
+            &sender,
+            <frame_system::Module<T>>::extrinsic_index(),
+            Self::kitties_count(),
+        );
+
+        payload.using_encoded(blake2_128)
+    }
+
+    // 冷却结束的区块
+    fn cooldown_end(generation: u32, now: T::BlockNumber) -> T::BlockNumber {
+        let factor: T::BlockNumber = generation.saturating_add(1).into();
+        now.saturating_add(T::BreedCooldown::get().saturating_mul(factor))
+    }
+
+    fn insert_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex, kitty: KittyOf<T>) -> DispatchResult {
         Kitties::<T>::insert(&owner, kitty_id, kitty.clone());
         KittyOwners::<T>::insert(kitty_id, &owner);
 
-        let mut kitty_vec = AccountKitties::<T>::take(&owner);
-        kitty_vec.push((kitty_id, kitty));
-        AccountKitties::<T>::insert(&owner, kitty_vec);
+        Self::add_owned_kitty(owner, kitty_id)?;
         KittiesCount::<T>::put(kitty_id);
         Ok(())
     }
 
-    fn do_breed(sender: &T::AccountId, kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> sp_std::result::Result<T::KittyIndex, DispatchError> {
-        let kitty1 = Self::kitties(&sender, kitty_id_1).ok_or(Error::<T>::InvalidaKittyId)?;
-        let kitty2 = Self::kitties(&sender, kitty_id_2).ok_or(Error::<T>::InvalidaKittyId)?;
+    // 创建一只初代kitty，质押token
+    fn do_create(sender: &T::AccountId) -> sp_std::result::Result<T::KittyIndex, DispatchError> {
+        let kitty_id = Self::next_kitty_id()?;
+
+        let dna = Self::random_value(sender);
+
+        let now = <system::Module<T>>::block_number();
+        let kitty = Kitty {
+            dna,
+            generation: 0,
+            birth: now,
+            cooldown_until: now,
+        };
+
+        let amount = T::DepositCalculator::deposit(DepositAction::Create, 0, Self::owned_kitties_count(sender));
+
+        Self::insert_kitty(sender, kitty_id, kitty)?;
+
+        KittyLockAmount::<T>::insert(kitty_id, amount);
+
+        Self::lock_funds(sender, amount)?;
+
+        Self::deposit_event(RawEvent::Created(sender.clone(), kitty_id));
+        Ok(kitty_id)
+    }
+
+    // 创世时直接生成kitty，按 create/breed 一样的规则质押
+    fn mint_genesis_kitty(owner: &T::AccountId, dna: [u8; 16], parents: Option<(T::KittyIndex, T::KittyIndex)>) {
+        let kitty_id = Self::next_kitty_id().expect("genesis kitties count overflow");
+        let now = <system::Module<T>>::block_number();
+        let (action, generation) = match parents {
+            Some((p1, p2)) => {
+                // 和 breed 一样，父母不能是同一只kitty，也不能是近亲
+                assert!(p1 != p2, "genesis kitty parents must be different kitties");
+                let g1 = Self::kitty_details(p1).expect("genesis kitty parent must be minted before its child").generation;
+                let g2 = Self::kitty_details(p2).expect("genesis kitty parent must be minted before its child").generation;
+                assert!(!Self::is_related(p1, p2), "genesis kitty parents must not be related");
+                (DepositAction::Breed, g1.max(g2).saturating_add(1))
+            },
+            None => (DepositAction::Create, 0),
+        };
+        let amount = T::DepositCalculator::deposit(action, generation, Self::owned_kitties_count(owner));
+
+        Self::insert_kitty(owner, kitty_id, Kitty { dna, generation, birth: now, cooldown_until: now })
+            .expect("genesis kitty owner has too many kitties");
+        KittyLockAmount::<T>::insert(kitty_id, amount);
+        T::Currency::reserve(owner, amount).expect("genesis kitty owner must have enough balance for the deposit");
+
+        if let Some((p1, p2)) = parents {
+            KittyParents::<T>::insert(kitty_id, (p1, p2));
+            KittyChildren::<T>::insert(p1, kitty_id, ());
+            KittyChildren::<T>::insert(p2, kitty_id, ());
+        }
+    }
+
+    // 更新已有的kitty
+    fn update_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex, kitty: KittyOf<T>) {
+        Kitties::<T>::insert(owner, kitty_id, kitty);
+    }
+
+    // 把kitty 加到拥有者列表的末尾，每个账户最多 MaxKittiesPerAccount 只
+    fn add_owned_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+        ensure!(Self::owned_kitties_count(owner) < T::MaxKittiesPerAccount::get(), Error::<T>::TooManyKitties);
+        Self::push_owned_kitty(owner, kitty_id);
+        Ok(())
+    }
+
+    // 不检查上限，迁移的时候用
+    fn push_owned_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+        let count = Self::owned_kitties_count(owner);
+        OwnedKitties::<T>::insert(owner, count, kitty_id);
+        OwnedKittiesIndex::<T>::insert(kitty_id, count);
+        OwnedKittiesCount::<T>::insert(owner, count.saturating_add(1));
+    }
+
+    // 从拥有者列表中删除kitty，用最后一只kitty 填补空位
+    fn remove_owned_kitty(owner: &T::AccountId, kitty_id: T::KittyIndex) {
+        let index = OwnedKittiesIndex::<T>::take(kitty_id);
+        let last = Self::owned_kitties_count(owner).saturating_sub(1);
+        if index != last {
+            if let Some(last_kitty_id) = Self::owned_kitty(owner, last) {
+                OwnedKitties::<T>::insert(owner, index, last_kitty_id);
+                OwnedKittiesIndex::<T>::insert(last_kitty_id, index);
+            }
+        }
+        OwnedKitties::<T>::remove(owner, last);
+        OwnedKittiesCount::<T>::insert(owner, last);
+    }
+
+    // 某账户所有的kitty
+    pub fn account_kitties(owner: &T::AccountId) -> Vec<(T::KittyIndex, KittyOf<T>)> {
+        (0..Self::owned_kitties_count(owner))
+            .filter_map(|index| Self::owned_kitty(owner, index))
+            .filter_map(|kitty_id| Self::kitties(owner, kitty_id).map(|kitty| (kitty_id, kitty)))
+            .collect()
+    }
+
+    // 质押token
+    fn lock_funds(locker: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+        // 这里其实已经判断了余额不足的 但是这个Error Event 还没找到怎么发送 BalanceNotEnough
+        T::Currency::reserve(locker, amount)
+            .map_err(|_| Error::<T>::BalanceNotEnough)?;
+
+        let now = <system::Module<T>>::block_number();
+        Self::deposit_event(RawEvent::LockFunds(locker.clone(), amount, now));
+        Ok(())
+    }
+
+    // 解除质押，并转移到 dest 账户
+    fn unlock_and_transfer(to_punish: &T::AccountId, dest: &T::AccountId, collateral: BalanceOf<T>) -> DispatchResult {
+        // If collateral is bigger than to_punish's reserved_balance, store what's left in overdraft.
+        let overdraft = T::Currency::unreserve(to_punish, collateral);
+
+        T::Currency::transfer(to_punish, dest, collateral - overdraft, AllowDeath)?;
+
+        let now = <system::Module<T>>::block_number();
+        Self::deposit_event(RawEvent::TransferFunds(to_punish.clone(), dest.clone(), collateral - overdraft, now));
+        Ok(())
+    }
+
+    // 买家付款给卖家，并转移kitty，任何一步失败都会回滚
+    #[transactional]
+    fn do_sell(seller: &T::AccountId, buyer: &T::AccountId, kitty_id: T::KittyIndex, price: BalanceOf<T>) -> DispatchResult {
+        T::Currency::transfer(buyer, seller, price, KeepAlive)
+            .map_err(|_| Error::<T>::BalanceNotEnough)?;
+        Self::do_transfer(seller, buyer, kitty_id)?;
+        KittyBoughtAt::<T>::insert(kitty_id, <system::Module<T>>::block_number());
+        Ok(())
+    }
+
+    // 保管kitty 的账户，出租期间是租借者，否则是拥有者
+    pub fn custodian(kitty_id: T::KittyIndex) -> Option<T::AccountId> {
+        match Self::rental(kitty_id) {
+            Some(rental) => Some(rental.borrower),
+            None => Self::kitty_owner(kitty_id),
+        }
+    }
+
+    // 确认 who 保管着这只kitty，返回拥有者
+    fn ensure_custodian(who: &T::AccountId, kitty_id: T::KittyIndex) -> sp_std::result::Result<T::AccountId, DispatchError> {
+        let owner = Self::kitty_owner(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+        match Self::rental(kitty_id) {
+            Some(rental) if rental.borrower == *who => Ok(owner),
+            Some(_) if owner == *who => Err(Error::<T>::KittyRented.into()),
+            None if owner == *who => Ok(owner),
+            _ => Err(Error::<T>::InvalidaKittyId.into()),
+        }
+    }
+
+    // 归还 now 区块到期的kitty，返回归还的数量
+    fn return_rentals(now: T::BlockNumber) -> u32 {
+        let mut returned = 0u32;
+        for kitty_id in RentalsEndAt::<T>::take(now) {
+            if let Some(rental) = Rentals::<T>::take(kitty_id) {
+                if let Some(owner) = Self::kitty_owner(kitty_id) {
+                    Self::deposit_event(RawEvent::RentalEnded(owner, rental.borrower, kitty_id));
+                }
+                returned = returned.saturating_add(1);
+            }
+        }
+        returned
+    }
+
+    // 奖励池账户，资金来自 fund_rewards
+    pub fn account_id() -> T::AccountId {
+        T::ModuleId::get().into_account()
+    }
+
+    // 质押这只kitty 每个区块的奖励，按稀有度加倍
+    fn staking_reward(kitty: &KittyOf<T>) -> BalanceOf<T> {
+        let multiplier: BalanceOf<T> = genes::decode(&kitty.dna).rarity.reward_multiplier().into();
+        T::StakingRewardPerBlock::get().saturating_mul(multiplier)
+    }
+
+    // 把上次结算到现在的奖励加到 pending 里，不保存
+    fn settle_rewards(who: &T::AccountId) -> StakingInfoOf<T> {
+        let now = <system::Module<T>>::block_number();
+        let mut info = Self::staker(who);
+        let blocks: BalanceOf<T> = now.saturating_sub(info.last_update).saturated_into::<u32>().into();
+        info.pending = info.pending.saturating_add(info.reward_rate.saturating_mul(blocks));
+        info.last_update = now;
+        info
+    }
+
+    // 没有质押也没有奖励的账户直接删除
+    fn update_staker(who: &T::AccountId, info: StakingInfoOf<T>) {
+        if info.reward_rate.is_zero() && info.pending.is_zero() {
+            Stakers::<T>::remove(who);
+        } else {
+            Stakers::<T>::insert(who, info);
+        }
+    }
+
+    // 删除 metadata，退还质押给设置的账户
+    fn clear_metadata(kitty_id: T::KittyIndex) {
+        Metadata::<T>::remove(kitty_id);
+        if let Some((depositor, deposit)) = MetadataDeposits::<T>::take(kitty_id) {
+            T::Currency::unreserve(&depositor, deposit);
+            Self::deposit_event(RawEvent::MetadataCleared(depositor, kitty_id));
+        }
+    }
+
+    // metadata 的质押改由新的拥有者承担，新的拥有者余额不够时清除 metadata
+    fn move_metadata_deposit(kitty_id: T::KittyIndex, to: &T::AccountId) {
+        if let Some((depositor, deposit)) = Self::metadata_deposit(kitty_id) {
+            if depositor == *to {
+                return;
+            }
+            if T::Currency::reserve(to, deposit).is_ok() {
+                T::Currency::unreserve(&depositor, deposit);
+                MetadataDeposits::<T>::insert(kitty_id, (to.clone(), deposit));
+            } else {
+                Self::clear_metadata(kitty_id);
+            }
+        }
+    }
+
+    // sender 是否可以转移 owner 的这只kitty
+    fn can_transfer(sender: &T::AccountId, owner: &T::AccountId, kitty_id: T::KittyIndex) -> bool {
+        sender == owner
+            || Self::approved(kitty_id).as_ref() == Some(sender)
+            || Self::is_approved_for_all(owner, sender)
+    }
+
+    // kitty 是否在英式或者荷兰式拍卖中
+    fn in_auction(kitty_id: T::KittyIndex) -> bool {
+        Auctions::<T>::contains_key(kitty_id) || DutchAuctions::<T>::contains_key(kitty_id)
+    }
+
+    // 荷兰式拍卖在 now 区块的价格
+    pub fn dutch_price(auction: &DutchAuctionOf<T>, now: T::BlockNumber) -> BalanceOf<T> {
+        if now >= auction.end {
+            return auction.end_price;
+        }
+        let elapsed: BalanceOf<T> = now.saturating_sub(auction.start).saturated_into::<u32>().into();
+        let duration: BalanceOf<T> = auction.end.saturating_sub(auction.start).saturated_into::<u32>().into();
+        let discount = auction.start_price.saturating_sub(auction.end_price).saturating_mul(elapsed) / duration;
+        auction.start_price.saturating_sub(discount)
+    }
+
+    // 结算到期的拍卖，没有出价或者结算失败时 kitty 留在卖家手里
+    fn settle_auction(kitty_id: T::KittyIndex, auction: AuctionOf<T>) {
+        if let Some((winner, price)) = auction.highest_bid {
+            T::Currency::unreserve(&winner, price);
+            if Self::do_sell(&auction.seller, &winner, kitty_id, price).is_ok() {
+                Self::deposit_event(RawEvent::AuctionSettled(auction.seller, winner, kitty_id, price));
+                return;
+            }
+        }
+        Self::deposit_event(RawEvent::AuctionExpired(auction.seller, kitty_id));
+    }
+
+    // 转移kitty的所有权，以及kitty对应的质押
+    fn do_transfer(from: &T::AccountId, to: &T::AccountId, kitty_id: T::KittyIndex) -> DispatchResult {
+        ensure!(!Self::in_auction(kitty_id), Error::<T>::KittyInAuction);
+        ensure!(!Rentals::<T>::contains_key(kitty_id), Error::<T>::KittyRented);
+        ensure!(!StakedKitties::<T>::contains_key(kitty_id), Error::<T>::KittyStaked);
+        let kitty = Kitties::<T>::take(from, kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+
+        // 变更 kitty的所有者关系
+        Self::remove_owned_kitty(from, kitty_id);
+        Self::add_owned_kitty(to, kitty_id)?;
+        Kitties::<T>::insert(to, kitty_id, kitty);
+        KittyOwners::<T>::insert(&kitty_id, to.clone());
+        // 换了主人之后，之前的挂单、配种报价和授权失效
+        KittyPrices::<T>::remove(kitty_id);
+        SireOffers::<T>::remove(kitty_id);
+        RentalOffers::<T>::remove(kitty_id);
+        KittyApprovals::<T>::remove(kitty_id);
+        KittyBoughtAt::<T>::remove(kitty_id);
+        if T::ClearMetadataOnTransfer::get() {
+            Self::clear_metadata(kitty_id);
+        } else {
+            Self::move_metadata_deposit(kitty_id, to);
+        }
+
+        // 获取kitty的质押数量
+        let amount = Self::lock_amount(kitty_id).ok_or(Error::<T>::InvalidaKittyId)?;
+        // 解除质押，并转移质押到拥有者账号
+        Self::unlock_and_transfer(from, to, amount)?;
+        // 把质押的token 质押到拥有者账号里 (会不会产生在上面解除质押，转移的过程中，toke还没到账，然后账户上没有足够的token去质押的情况呢？也就是，这里是同步的，不是异步执行的吧)
+        Self::lock_funds(to, amount)?;
+        Ok(())
+    }
+
+    // kitty 的所有children
+    pub fn kitty_children(kitty_id: T::KittyIndex) -> Vec<T::KittyIndex> {
+        KittyChildren::<T>::iter_prefix(kitty_id).map(|(child, _)| child).collect()
+    }
+
+    // 按 kitty id 查询kitty
+    pub fn kitty_details(kitty_id: T::KittyIndex) -> Option<KittyOf<T>> {
+        Self::kitty_owner(kitty_id).and_then(|owner| Self::kitties(owner, kitty_id))
+    }
+
+    // 从 DNA 解码出来的kitty 特征
+    pub fn kitty_traits(kitty_id: T::KittyIndex) -> Option<KittyTraits> {
+        Self::kitty_details(kitty_id).map(|kitty| genes::decode(&kitty.dna))
+    }
+
+    // kitty 的父母和children
+    pub fn kitty_lineage(kitty_id: T::KittyIndex) -> KittyLineage<T::KittyIndex> {
+        KittyLineage {
+            parents: Self::kitty_parents(kitty_id),
+            children: Self::kitty_children(kitty_id),
+            deceased: Self::is_deceased(kitty_id),
+        }
+    }
+
+    // kitty 自己和 depth 代以内的祖先
+    fn ancestors(kitty_id: T::KittyIndex, depth: u32) -> Vec<T::KittyIndex> {
+        let mut ancestors = Vec::new();
+        ancestors.push(kitty_id);
+        let mut current = ancestors.clone();
+        for _ in 0..depth {
+            let mut parents = Vec::new();
+            for (p1, p2) in current.iter().filter_map(|id| Self::kitty_parents(id)) {
+                parents.push(p1);
+                parents.push(p2);
+            }
+            if parents.is_empty() {
+                break;
+            }
+            ancestors.extend(parents.iter().copied());
+            current = parents;
+        }
+        ancestors
+    }
+
+    // 实际检查的祖先代数，限制在 1 到 MAX_INBREEDING_DEPTH 之间
+    pub fn inbreeding_depth() -> u32 {
+        T::InbreedingDepth::get().max(1).min(MAX_INBREEDING_DEPTH)
+    }
+
+    // 两只kitty 在 InbreedingDepth 代以内有共同的祖先，或者一只是另一只的祖先
+    fn is_related(kitty_id_1: T::KittyIndex, kitty_id_2: T::KittyIndex) -> bool {
+        let depth = Self::inbreeding_depth();
+        let ancestors_1 = Self::ancestors(kitty_id_1, depth);
+        Self::ancestors(kitty_id_2, depth).iter().any(|id| ancestors_1.contains(id))
+    }
+
+    // 繁殖小kitty，质押token，并更新kitty 关系。kitty_id_2 由 sire_owner 保管，小kitty属于 sender
+    fn breed_child(
+        sender: &T::AccountId,
+        kitty_id_1: T::KittyIndex,
+        sire_owner: &T::AccountId,
+        kitty_id_2: T::KittyIndex,
+    ) -> sp_std::result::Result<T::KittyIndex, DispatchError> {
+        let owned = Self::owned_kitties_count(sender);
+
+        let new_kitty_id = Self::do_breed(sender, kitty_id_1, sire_owner, kitty_id_2)?;
+        let generation = Self::kitties(sender, new_kitty_id).map(|kitty| kitty.generation).unwrap_or_default();
+        let amount = T::DepositCalculator::deposit(DepositAction::Breed, generation, owned);
+        // 质押token
+        KittyLockAmount::<T>::insert(&new_kitty_id, amount.clone());
+
+        Self::lock_funds(sender, amount)?;
+
+        // 更新kitty 父母和children关系，只涉及这三只kitty
+        KittyParents::<T>::insert(new_kitty_id, (kitty_id_1, kitty_id_2));
+        KittyChildren::<T>::insert(kitty_id_1, new_kitty_id, ());
+        KittyChildren::<T>::insert(kitty_id_2, new_kitty_id, ());
+
+        Ok(new_kitty_id)
+    }
+
+    fn do_breed(
+        sender: &T::AccountId,
+        kitty_id_1: T::KittyIndex,
+        sire_owner: &T::AccountId,
+        kitty_id_2: T::KittyIndex,
+    ) -> sp_std::result::Result<T::KittyIndex, DispatchError> {
+        // 租借者也可以用租来的kitty 繁殖，kitty 还是存在拥有者名下
+        let owner1 = Self::ensure_custodian(sender, kitty_id_1)?;
+        let owner2 = Self::ensure_custodian(sire_owner, kitty_id_2)?;
+        let mut kitty1 = Self::kitties(&owner1, kitty_id_1).ok_or(Error::<T>::InvalidaKittyId)?;
+        let mut kitty2 = Self::kitties(&owner2, kitty_id_2).ok_or(Error::<T>::InvalidaKittyId)?;
 
         ensure!(kitty_id_1 != kitty_id_2, Error::<T>::RequireDifferentParent);
+        ensure!(
+            !StakedKitties::<T>::contains_key(kitty_id_1) && !StakedKitties::<T>::contains_key(kitty_id_2),
+            Error::<T>::KittyStaked
+        );
+        let now = <system::Module<T>>::block_number();
+        ensure!(kitty1.cooldown_until <= now && kitty2.cooldown_until <= now, Error::<T>::KittyInCooldown);
+        ensure!(!Self::is_related(kitty_id_1, kitty_id_2), Error::<T>::InbreedingNotAllowed);
         let kitty_id = Self::next_kitty_id()?;
 
-        let kitty1_dna = kitty1.0;
-        let kitty2_dna = kitty2.0;
-        let selector = Self::random_value(&sender);
-        let mut new_dna = [0u8; 16];
-        for i in 0..kitty1_dna.len() {
-            new_dna[i] = combine_dna(kitty1_dna[i], kitty2_dna[i], selector[i]);
-        }
-        Self::insert_kitty(sender, kitty_id, Kitty(new_dna))?;
+        let mut new_dna = genes::breed(&kitty1.dna, &kitty2.dna, &Self::random_value(&sender));
+        let mutations = genes::mutate(
+            &mut new_dna,
+            &kitty1.dna,
+            &kitty2.dna,
+            &Self::mutation_random(&sender),
+            T::MutationRate::get(),
+        );
+
+        // 父母进入冷却
+        kitty1.cooldown_until = Self::cooldown_end(kitty1.generation, now);
+        kitty2.cooldown_until = Self::cooldown_end(kitty2.generation, now);
+        let generation = kitty1.generation.max(kitty2.generation).saturating_add(1);
+        Self::update_kitty(&owner1, kitty_id_1, kitty1);
+        Self::update_kitty(&owner2, kitty_id_2, kitty2);
+
+        let kitty = Kitty {
+            dna: new_dna,
+            generation,
+            birth: now,
+            cooldown_until: Self::cooldown_end(generation, now),
+        };
+        Self::insert_kitty(sender, kitty_id, kitty)?;
+
+        Self::deposit_event(RawEvent::Born(sender.clone(), kitty_id, mutations));
         Ok(kitty_id)
     }
 }
\ No newline at end of file
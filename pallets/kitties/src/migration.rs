@@ -0,0 +1,130 @@
+// 链上数据迁移
+use super::*;
+use frame_support::{
+    IterableStorageDoubleMap,
+    storage::migration::{get_storage_value, put_storage_value, take_storage_value, StorageIterator},
+};
+use sp_std::collections::btree_map::BTreeMap;
+
+// 旧版本的 Kitty 只有 16 字节的 DNA, 编码和 [u8; 16] 一样
+pub type OldKitty = [u8; 16];
+
+// V3 之前的 kitty 关系，全部kitty 存在一个 Vec 里
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct OldKittyNode<KittyIndex> {
+    // 自己
+    pub _self: KittyIndex,
+    // 父母双方，create的kitty可能是None
+    pub companion: Option<(KittyIndex, KittyIndex)>,
+    // kitty的children
+    pub children: Vec<KittyIndex>,
+}
+
+// 读取旧的 KittyNodeStorage
+fn old_kitty_nodes<T: Trait>() -> Vec<OldKittyNode<T::KittyIndex>> {
+    get_storage_value(b"Kitties", b"KittyNodeStorage", &[]).unwrap_or_default()
+}
+
+// V1 -> V2: Kitty 从 DNA 变成带代数、出生区块、冷却区块的结构
+// KittyNode 的结构没有变化，这里用它来恢复每只kitty的代数
+pub fn migrate_to_v2<T: Trait>() -> Weight {
+    if StorageVersion::get() != Releases::V1_0_0 {
+        return 0;
+    }
+
+    // KittyNodeStorage 按创建顺序 push，父母一定在孩子前面
+    let nodes = old_kitty_nodes::<T>();
+    let mut generations: BTreeMap<T::KittyIndex, u32> = BTreeMap::new();
+    for node in nodes.iter() {
+        let generation = match node.companion {
+            Some((p1, p2)) => {
+                let g1 = generations.get(&p1).copied().unwrap_or_default();
+                let g2 = generations.get(&p2).copied().unwrap_or_default();
+                g1.max(g2).saturating_add(1)
+            },
+            None => 0,
+        };
+        generations.insert(node._self, generation);
+    }
+
+    // 旧的kitty 不知道出生区块，用升级时的区块，冷却直接结束
+    let now = <system::Module<T>>::block_number();
+    let to_kitty = |kitty_id: &T::KittyIndex, dna: OldKitty| Kitty {
+        dna,
+        generation: generations.get(kitty_id).copied().unwrap_or_default(),
+        birth: now,
+        cooldown_until: now,
+    };
+
+    Kitties::<T>::translate::<OldKitty, _>(|_owner, kitty_id, dna| Some(to_kitty(&kitty_id, dna)));
+    // AccountKitties 在 V4 删除了，这里直接按原来的key 改写
+    let account_kitties: Vec<_> =
+        StorageIterator::<Vec<(T::KittyIndex, OldKitty)>>::new(b"Kitties", b"AccountKitties").collect();
+    for (key, kitty_vec) in account_kitties {
+        let kitty_vec: Vec<(T::KittyIndex, KittyOf<T>)> = kitty_vec.into_iter()
+            .map(|(kitty_id, dna)| (kitty_id, to_kitty(&kitty_id, dna)))
+            .collect();
+        put_storage_value(b"Kitties", b"AccountKitties", &key, kitty_vec);
+    }
+
+    StorageVersion::put(Releases::V2_0_0);
+
+    // 每只kitty 一个 node，账户数量不会多于kitty数量
+    let count = nodes.len() as Weight;
+    T::DbWeight::get().reads_writes(2 * count + 2, 2 * count + 1)
+}
+
+// V2 -> V3: KittyNodeStorage 拆成每只kitty 的 KittyParents 和 KittyChildren
+pub fn migrate_to_v3<T: Trait>() -> Weight {
+    if StorageVersion::get() != Releases::V2_0_0 {
+        return 0;
+    }
+
+    let nodes: Vec<OldKittyNode<T::KittyIndex>> =
+        take_storage_value(b"Kitties", b"KittyNodeStorage", &[]).unwrap_or_default();
+    let mut writes: Weight = 2;
+    for node in nodes.iter() {
+        if let Some(parents) = node.companion {
+            KittyParents::<T>::insert(node._self, parents);
+            writes += 1;
+        }
+        for child in node.children.iter() {
+            KittyChildren::<T>::insert(node._self, child, ());
+            writes += 1;
+        }
+    }
+
+    StorageVersion::put(Releases::V3_0_0);
+
+    T::DbWeight::get().reads_writes(2, writes)
+}
+
+// V3 -> V4: AccountKitties 的 Vec 换成 OwnedKitties 索引，保持原来的顺序
+pub fn migrate_to_v4<T: Trait>() -> Weight {
+    if StorageVersion::get() != Releases::V3_0_0 {
+        return 0;
+    }
+
+    let mut reads: Weight = 1;
+    let mut writes: Weight = 1;
+    let account_kitties =
+        StorageIterator::<Vec<(T::KittyIndex, KittyOf<T>)>>::new(b"Kitties", b"AccountKitties").drain();
+    for (key, kitty_vec) in account_kitties {
+        reads += 1;
+        writes += 1;
+        // key 是 blake2_128_concat(owner)，前16字节是hash
+        let owner = match key.get(16..).and_then(|mut raw| T::AccountId::decode(&mut raw).ok()) {
+            Some(owner) => owner,
+            None => continue,
+        };
+        for (kitty_id, _) in kitty_vec {
+            Module::<T>::push_owned_kitty(&owner, kitty_id);
+            reads += 1;
+            writes += 3;
+        }
+    }
+
+    StorageVersion::put(Releases::V4_0_0);
+
+    T::DbWeight::get().reads_writes(reads, writes)
+}
@@ -8,7 +8,7 @@ pub use frame_support::{
 	traits::{Currency, Get,},
 };
 use sp_runtime::{
-	traits::{BlakeTwo256, IdentityLookup}, testing::Header, Perbill,
+	traits::{BlakeTwo256, IdentityLookup}, testing::Header, ModuleId, Perbill, Permill,
 };
 
 use pallet_balances as balances;
@@ -60,7 +60,18 @@ parameter_types! {
 	pub const TransferFee: u64 = 0;
 	pub const CreationFee: u64 = 0;
 
-	pub const KittyReserveFundsConst: u64 = 10_000_000_000_000;
+	pub const KittyReserveFundsConst: u64 = 100;
+	pub const BreedCooldown: u64 = 5;
+	pub const MaxKittiesPerAccount: u32 = 10;
+	pub const MaxNameLength: u32 = 8;
+	pub const MaxDescriptionLength: u32 = 32;
+	pub const MaxUriLength: u32 = 32;
+	pub const MaxBatchSize: u32 = 3;
+	pub const MaxAuctionsPerBlock: u32 = 2;
+	pub const MaxRentalsPerBlock: u32 = 2;
+	pub const MetadataDepositPerByte: u64 = 2;
+	pub const KittiesModuleId: ModuleId = ModuleId(*b"py/kitty");
+	pub const StakingRewardPerBlock: u64 = 10;
 }
 
 impl system::Trait for Test {
@@ -104,6 +115,30 @@ impl pallet_balances::Trait for Test {
 thread_local! {
 	static RANDOM_PAYLOAD: RefCell<H256> = RefCell::new(Default::default());
 	static EXISTENTIAL_DEPOSIT: RefCell<Balance> = RefCell::new(0);
+	static MUTATION_RATE: RefCell<Permill> = RefCell::new(Permill::zero());
+	static INBREEDING_DEPTH: RefCell<u32> = RefCell::new(1);
+	static CLEAR_METADATA_ON_TRANSFER: RefCell<bool> = RefCell::new(false);
+}
+
+pub struct ClearMetadataOnTransfer;
+impl Get<bool> for ClearMetadataOnTransfer {
+	fn get() -> bool {
+		CLEAR_METADATA_ON_TRANSFER.with(|v| *v.borrow())
+	}
+}
+
+pub struct InbreedingDepth;
+impl Get<u32> for InbreedingDepth {
+	fn get() -> u32 {
+		INBREEDING_DEPTH.with(|v| *v.borrow())
+	}
+}
+
+pub struct MutationRate;
+impl Get<Permill> for MutationRate {
+	fn get() -> Permill {
+		MUTATION_RATE.with(|v| *v.borrow())
+	}
 }
 
 pub struct MockRandom;
@@ -119,12 +154,34 @@ impl Trait for Test {
 	type Randomness = MockRandom;
 	type KittyIndex = u32;
 	type Currency = Balances;
-	type KittyReserveFunds = u8;
+	type MaxKittiesPerAccount = MaxKittiesPerAccount;
+	type DepositCalculator = FixedDeposit<KittyReserveFundsConst>;
+	type BreedCooldown = BreedCooldown;
+	type MaxNameLength = MaxNameLength;
+	type MaxDescriptionLength = MaxDescriptionLength;
+	type MaxUriLength = MaxUriLength;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ClearMetadataOnTransfer = ClearMetadataOnTransfer;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxAuctionsPerBlock = MaxAuctionsPerBlock;
+	type MaxRentalsPerBlock = MaxRentalsPerBlock;
+	type InbreedingDepth = InbreedingDepth;
+	type MutationRate = MutationRate;
+	type ModuleId = KittiesModuleId;
+	type StakingRewardPerBlock = StakingRewardPerBlock;
+	type SlashOrigin = system::EnsureRoot<u64>;
+	type FundOrigin = system::EnsureRoot<u64>;
+	type WeightInfo = ();
 }
 
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
+	new_test_ext_with_kitties(vec![])
+}
+
+// 带有创世kitty 的测试环境
+pub fn new_test_ext_with_kitties(kitties: Vec<(u64, [u8; 16], Option<(u32, u32)>)>) -> sp_io::TestExternalities {
 	let mut t = frame_system::GenesisConfig::default()
 		.build_storage::<Test>()
 		.unwrap();
@@ -135,11 +192,31 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 	}
 	.assimilate_storage(&mut t)
 	.unwrap();
+
+	GenesisConfig::<Test> { kitties }
+		.assimilate_storage(&mut t)
+		.unwrap();
 	let mut ext: sp_io::TestExternalities = t.into();
 	ext.execute_with(|| System::set_block_number(1));
 	ext
 }
 
+pub fn set_existential_deposit(amount: Balance) {
+	EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = amount);
+}
+
+pub fn set_mutation_rate(rate: Permill) {
+	MUTATION_RATE.with(|v| *v.borrow_mut() = rate);
+}
+
+pub fn set_inbreeding_depth(depth: u32) {
+	INBREEDING_DEPTH.with(|v| *v.borrow_mut() = depth);
+}
+
+pub fn set_clear_metadata_on_transfer(clear: bool) {
+	CLEAR_METADATA_ON_TRANSFER.with(|v| *v.borrow_mut() = clear);
+}
+
 pub fn last_event() -> Event {
     System::events().last().unwrap().event.clone()
 }
\ No newline at end of file
@@ -1,13 +1,26 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_noop};
+use crate::{Error, MAX_INBREEDING_DEPTH, mock::*, migration::OldKittyNode};
+use crate::genes::{self, Body, EyeColour, KittyTraits, Pattern, RarityTier};
+use codec::Encode;
+use frame_support::{
+	assert_ok, assert_noop,
+	storage::migration::{get_storage_value, put_storage_value},
+	traits::{Currency, OnFinalize, OnInitialize, OnRuntimeUpgrade},
+	Blake2_128Concat, StorageHasher,
+};
+use sp_runtime::traits::BadOrigin;
 
 
 #[test]
 fn can_create_kitty() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(KModule::create(Origin::signed(1), 100));
+		assert_ok!(KModule::create(Origin::signed(1)));
 
-        let kt = Kitty([39, 140, 77, 194, 163, 1, 154, 220, 108, 18, 30, 32, 100, 223, 46, 1]);
+        let kt = Kitty {
+			dna: [250, 147, 40, 122, 202, 228, 210, 154, 251, 23, 196, 66, 49, 235, 123, 53],
+			generation: 0,
+			birth: 1,
+			cooldown_until: 1,
+		};
         assert_eq!(KModule::kitties(1, 1), Some(kt.clone()));
 		assert_eq!(KModule::kitties_count(), 1);
 
@@ -20,52 +33,42 @@ fn can_create_kitty() {
 }
 
 #[test]
-fn can_reserve_funds() {
+fn signed_origin_cannot_slash_deposit() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(KModule::reserve_funds(Origin::signed(1), 1, 100));
+		assert_ok!(KModule::create(Origin::signed(1)));
 
-		assert_eq!(last_event(), Event::kitties(RawEvent::LockFunds(1, 100, 1)));
-
-		// Test and see if (1, 5000) holds 账户可转账余额
-		assert_eq!(Balances::free_balance(&1), 9900);
-		// 账户锁仓余额
+		// 普通账户不能动别人的质押
+		assert_noop!(KModule::slash_deposit(Origin::signed(2), 1, 2), BadOrigin);
+		assert_noop!(KModule::slash_deposit(Origin::signed(1), 1, 2), BadOrigin);
 		assert_eq!(Balances::reserved_balance(&1), 100);
-	});
-}
-#[test]
-fn reserve_funds_failed_not_enough_balance() {
-	new_test_ext().execute_with(|| {
-		assert_noop!(KModule::reserve_funds(Origin::signed(1), 1, 12000), Error::<Test>::BalanceNotEnough);
+		assert_eq!(Balances::free_balance(&2), 11000);
 	});
 }
 
 #[test]
-fn can_unreserve_and_transfer() {
+fn root_can_slash_deposit() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(KModule::reserve_funds(Origin::signed(1), 1, 100));
-		// Test and see if (1, 5000) holds 账户可转账余额
-		assert_eq!(Balances::free_balance(&1), 9900);
-		// 账户锁仓余额
-		assert_eq!(Balances::reserved_balance(&1), 100);
-		assert_eq!(last_event(), Event::kitties(RawEvent::LockFunds(1, 100, 1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
 
-		// 转移质押token
-		assert_ok!(KModule::unreserve_and_transfer(Origin::signed(1), 1, 2, 100));
-		// 转移质押event
-		assert_eq!(last_event(), Event::kitties(RawEvent::TransferFunds(1, 2, 100, 1)));
+		assert_noop!(KModule::slash_deposit(Origin::root(), 2, 2), Error::<Test>::InvalidaKittyId);
+		assert_ok!(KModule::slash_deposit(Origin::root(), 1, 2));
 
 		assert_eq!(Balances::reserved_balance(&1), 0);
-		assert_eq!(Balances::reserved_balance(&2), 0);
 		assert_eq!(Balances::free_balance(&1), 9900);
 		assert_eq!(Balances::free_balance(&2), 11100);
+		assert_eq!(KModule::lock_amount(1), Some(0));
+		assert_eq!(last_event(), Event::kitties(RawEvent::DepositSlashed(1, 2, 1, 100)));
 
+		// 质押罚没之后 kitty 依然可以转移
+		assert_ok!(KModule::transfer(Origin::signed(1), 3, 1));
+		assert_eq!(Balances::reserved_balance(&3), 0);
 	});
 }
 
 #[test]
 fn can_transfer() {
     new_test_ext().execute_with(|| {
-        assert_ok!(KModule::create(Origin::signed(1), 100));
+        assert_ok!(KModule::create(Origin::signed(1)));
 
         assert_eq!(KModule::kitties_count(), 1);
 
@@ -87,22 +90,27 @@ fn can_transfer() {
 #[test]
 fn can_breed() {
 	new_test_ext().execute_with(|| {
-		assert_ok!(KModule::create(Origin::signed(1), 100));
+		assert_ok!(KModule::create(Origin::signed(1)));
 		// transfer kitty 检查账户1质押token
 		assert_eq!(Balances::free_balance(&1), 9900);
 		assert_eq!(Balances::reserved_balance(&1), 100);
 		// 再生一个
-		assert_ok!(KModule::create(Origin::signed(1), 100));
+		assert_ok!(KModule::create(Origin::signed(1)));
 		// transfer kitty 检查账户1质押token
 		assert_eq!(Balances::free_balance(&1), 9800);
 		assert_eq!(Balances::reserved_balance(&1), 200);
 
 		// 边界检查
-		assert_noop!(KModule::breed(Origin::signed(1), 0, 3, 100), Error::<Test>::InvalidaKittyId);
-		assert_noop!(KModule::breed(Origin::signed(1), 1, 1, 100), Error::<Test>::RequireDifferentParent);
+		assert_noop!(KModule::breed(Origin::signed(1), 0, 3), Error::<Test>::InvalidaKittyId);
+		assert_noop!(KModule::breed(Origin::signed(1), 1, 1), Error::<Test>::RequireDifferentParent);
 		// do breed
-		assert_ok!(KModule::breed(Origin::signed(1), 1, 2, 100));
-		let kt = Kitty([39, 140, 77, 194, 163, 1, 154, 220, 108, 18, 30, 32, 100, 223, 46, 1]);
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+		let kt = Kitty {
+			dna: [250, 30, 250, 40, 202, 193, 210, 76, 97, 23, 244, 73, 177, 59, 120, 125],
+			generation: 1,
+			birth: 1,
+			cooldown_until: 11,
+		};
 		assert_eq!(KModule::kitties(1, 3), Some(kt.clone()));
 		assert_eq!(KModule::kitties_count(), 3);
 
@@ -112,4 +120,942 @@ fn can_breed() {
 
 		assert_eq!(last_event(), Event::kitties(RawEvent::Created(1, 3)));
 	})
-}
\ No newline at end of file
+}
+#[test]
+fn can_list_and_buy() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+
+		// 只有拥有者可以挂单
+		assert_noop!(KModule::list(Origin::signed(2), 1, 500), Error::<Test>::InvalidaKittyId);
+		// 没有挂单不可以购买
+		assert_noop!(KModule::buy(Origin::signed(2), 1, 500), Error::<Test>::KittyNotForSale);
+
+		assert_ok!(KModule::list(Origin::signed(1), 1, 500));
+		assert_eq!(KModule::kitty_price(1), Some(500));
+		assert_eq!(last_event(), Event::kitties(RawEvent::Listed(1, 1, 500)));
+
+		// 不可以购买自己的kitty
+		assert_noop!(KModule::buy(Origin::signed(1), 1, 500), Error::<Test>::BuyOwnKitty);
+
+		assert_ok!(KModule::buy(Origin::signed(2), 1, 500));
+		assert_eq!(KModule::kitty_owner(1), Some(2));
+		assert_eq!(KModule::kitty_price(1), None);
+		assert_eq!(KModule::kitties(1, 1), None);
+		assert!(KModule::kitties(2, 1).is_some());
+
+		// 卖家收到货款，质押随kitty转移到买家
+		assert_eq!(Balances::free_balance(&1), 10400);
+		assert_eq!(Balances::reserved_balance(&1), 0);
+		assert_eq!(Balances::free_balance(&2), 10500);
+		assert_eq!(Balances::reserved_balance(&2), 100);
+
+		assert_eq!(last_event(), Event::kitties(RawEvent::Sold(1, 2, 1, 500)));
+
+		// 记录买入的区块，再次转移后清除
+		assert_eq!(KModule::bought_at(1), Some(1));
+		assert_ok!(KModule::transfer(Origin::signed(2), 3, 1));
+		assert_eq!(KModule::bought_at(1), None);
+	});
+}
+
+#[test]
+fn buy_rejects_raised_price() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::list(Origin::signed(1), 1, 500));
+
+		// 卖家抢先提高价格，买家按原来的价格出价失败
+		assert_ok!(KModule::list(Origin::signed(1), 1, 800));
+		assert_noop!(KModule::buy(Origin::signed(2), 1, 500), Error::<Test>::PriceTooHigh);
+		assert_ok!(KModule::buy(Origin::signed(2), 1, 800));
+		assert_eq!(Balances::free_balance(&2), 11000 - 800);
+	});
+}
+
+#[test]
+fn can_unlist() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_noop!(KModule::unlist(Origin::signed(1), 1), Error::<Test>::KittyNotForSale);
+
+		assert_ok!(KModule::list(Origin::signed(1), 1, 500));
+		assert_ok!(KModule::unlist(Origin::signed(1), 1));
+		assert_eq!(KModule::kitty_price(1), None);
+		assert_eq!(last_event(), Event::kitties(RawEvent::Unlisted(1, 1)));
+
+		assert_noop!(KModule::buy(Origin::signed(2), 1, 500), Error::<Test>::KittyNotForSale);
+	});
+}
+
+#[test]
+fn can_auction() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+
+		assert_noop!(KModule::start_auction(Origin::signed(1), 1, 500, 1), Error::<Test>::InvalidAuctionEnd);
+		assert_ok!(KModule::start_auction(Origin::signed(1), 1, 500, 3));
+		assert_eq!(last_event(), Event::kitties(RawEvent::AuctionStarted(1, 1, 500, 3)));
+
+		// 拍卖期间不能转移
+		assert_noop!(KModule::transfer(Origin::signed(1), 2, 1), Error::<Test>::KittyInAuction);
+
+		assert_noop!(KModule::bid(Origin::signed(1), 1, 600), Error::<Test>::BuyOwnKitty);
+		assert_noop!(KModule::bid(Origin::signed(2), 1, 400), Error::<Test>::BidTooLow);
+		assert_ok!(KModule::bid(Origin::signed(2), 1, 600));
+		assert_eq!(Balances::reserved_balance(&2), 600);
+
+		// 出价被超过，退还质押
+		assert_noop!(KModule::bid(Origin::signed(3), 1, 600), Error::<Test>::BidTooLow);
+		assert_ok!(KModule::bid(Origin::signed(3), 1, 700));
+		assert_eq!(Balances::reserved_balance(&2), 0);
+		assert_eq!(Balances::reserved_balance(&3), 700);
+		assert_eq!(last_event(), Event::kitties(RawEvent::BidPlaced(3, 1, 700)));
+
+		System::set_block_number(3);
+		assert_noop!(KModule::bid(Origin::signed(2), 1, 800), Error::<Test>::AuctionAlreadyEnded);
+		KModule::on_finalize(3);
+
+		assert_eq!(KModule::auction(1), None);
+		assert_eq!(KModule::kitty_owner(1), Some(3));
+		assert_eq!(Balances::free_balance(&1), 10600);
+		assert_eq!(Balances::reserved_balance(&1), 0);
+		assert_eq!(Balances::free_balance(&3), 11300);
+		assert_eq!(Balances::reserved_balance(&3), 100);
+		assert_eq!(last_event(), Event::kitties(RawEvent::AuctionSettled(1, 3, 1, 700)));
+	});
+}
+
+#[test]
+fn auctions_ending_in_a_block_are_capped() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+
+		assert_ok!(KModule::start_auction(Origin::signed(1), 1, 500, 3));
+		assert_ok!(KModule::start_auction(Origin::signed(1), 2, 500, 3));
+		assert_noop!(KModule::start_auction(Origin::signed(1), 3, 500, 3), Error::<Test>::TooManyAuctions);
+		assert_ok!(KModule::start_auction(Origin::signed(1), 3, 500, 4));
+	});
+}
+
+#[test]
+fn auction_without_bid_expires() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::start_auction(Origin::signed(1), 1, 500, 2));
+
+		System::set_block_number(2);
+		KModule::on_finalize(2);
+
+		assert_eq!(KModule::auction(1), None);
+		assert_eq!(KModule::kitty_owner(1), Some(1));
+		assert_eq!(last_event(), Event::kitties(RawEvent::AuctionExpired(1, 1)));
+	});
+}
+
+#[test]
+fn can_dutch_auction() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+
+		assert_noop!(
+			KModule::start_dutch_auction(Origin::signed(1), 1, 500, 1000, 11),
+			Error::<Test>::InvalidAuctionPrice
+		);
+		assert_ok!(KModule::start_dutch_auction(Origin::signed(1), 1, 1000, 500, 11));
+		assert_eq!(last_event(), Event::kitties(RawEvent::DutchAuctionStarted(1, 1, 1000, 500, 11)));
+
+		// 荷兰式拍卖期间不能挂单和转移
+		assert_noop!(KModule::list(Origin::signed(1), 1, 100), Error::<Test>::KittyInAuction);
+		assert_noop!(KModule::transfer(Origin::signed(1), 2, 1), Error::<Test>::KittyInAuction);
+		assert_noop!(KModule::buy_dutch(Origin::signed(1), 1, 1000), Error::<Test>::BuyOwnKitty);
+
+		// 区块1到11 价格从1000降到500，每个区块降50
+		let auction = KModule::dutch_auction(1).unwrap();
+		assert_eq!(KModule::dutch_price(&auction, 1), 1000);
+		assert_eq!(KModule::dutch_price(&auction, 6), 750);
+		assert_eq!(KModule::dutch_price(&auction, 20), 500);
+
+		System::set_block_number(5);
+		assert_ok!(KModule::buy_dutch(Origin::signed(2), 1, 800));
+		assert_eq!(KModule::dutch_auction(1), None);
+		assert_eq!(KModule::kitty_owner(1), Some(2));
+		assert_eq!(Balances::free_balance(&1), 10700);
+		assert_eq!(Balances::free_balance(&2), 10200);
+		assert_eq!(Balances::reserved_balance(&2), 100);
+		assert_eq!(last_event(), Event::kitties(RawEvent::DutchAuctionSold(1, 2, 1, 800)));
+	});
+}
+
+#[test]
+fn buy_dutch_rejects_restarted_auction() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::start_dutch_auction(Origin::signed(1), 1, 1000, 500, 11));
+		System::set_block_number(5);
+
+		// 卖家抢先取消，再用更高的起拍价重新开始
+		assert_ok!(KModule::cancel_dutch_auction(Origin::signed(1), 1));
+		assert_ok!(KModule::start_dutch_auction(Origin::signed(1), 1, 2000, 500, 11));
+		assert_noop!(KModule::buy_dutch(Origin::signed(2), 1, 800), Error::<Test>::PriceTooHigh);
+		assert_ok!(KModule::buy_dutch(Origin::signed(2), 1, 2000));
+		assert_eq!(last_event(), Event::kitties(RawEvent::DutchAuctionSold(1, 2, 1, 2000)));
+	});
+}
+
+#[test]
+fn can_breed_with_sire() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(2)));
+
+		assert_noop!(KModule::breed_with_sire(Origin::signed(2), 2, 1, 300), Error::<Test>::SireOfferNotExist);
+		assert_noop!(KModule::offer_sire(Origin::signed(2), 1, 300, 10), Error::<Test>::InvalidaKittyId);
+		assert_ok!(KModule::offer_sire(Origin::signed(1), 1, 300, 10));
+		assert_eq!(last_event(), Event::kitties(RawEvent::SireOffered(1, 1, 300, 10)));
+
+		// 只能用自己的kitty 去配种
+		assert_noop!(KModule::breed_with_sire(Origin::signed(3), 2, 1, 300), Error::<Test>::InvalidaKittyId);
+		assert_ok!(KModule::breed_with_sire(Origin::signed(2), 2, 1, 300));
+
+		// 小kitty 归发起者，配种费付给种猫拥有者
+		assert_eq!(KModule::kitty_owner(3), Some(2));
+		assert_eq!(Balances::free_balance(&1), 10200);
+		assert_eq!(Balances::free_balance(&2), 10500);
+		assert_eq!(Balances::reserved_balance(&2), 200);
+		assert_eq!(KModule::kitty_parents(3), Some((2, 1)));
+		assert_eq!(KModule::kitty_children(1), vec![3]);
+		assert_eq!(last_event(), Event::kitties(RawEvent::Created(2, 3)));
+
+		System::set_block_number(11);
+		assert_noop!(KModule::breed_with_sire(Origin::signed(2), 2, 1, 300), Error::<Test>::SireOfferExpired);
+
+		assert_ok!(KModule::withdraw_sire_offer(Origin::signed(1), 1));
+		assert_eq!(KModule::sire_offer(1), None);
+	});
+}
+
+#[test]
+fn breed_with_sire_rejects_raised_fee() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(2)));
+		assert_ok!(KModule::offer_sire(Origin::signed(1), 1, 300, 10));
+
+		// 种猫拥有者抢先撤回，再用更高的配种费报价
+		assert_ok!(KModule::withdraw_sire_offer(Origin::signed(1), 1));
+		assert_ok!(KModule::offer_sire(Origin::signed(1), 1, 900, 10));
+		assert_noop!(KModule::breed_with_sire(Origin::signed(2), 2, 1, 300), Error::<Test>::PriceTooHigh);
+		assert_ok!(KModule::breed_with_sire(Origin::signed(2), 2, 1, 900));
+		assert_eq!(Balances::free_balance(&1), 10000 - 100 + 900);
+	});
+}
+
+#[test]
+fn breed_respects_cooldown() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+
+		// 父母进入冷却 1 + 5 * (0 + 1)
+		assert_eq!(KModule::kitties(1, 1).unwrap().cooldown_until, 6);
+		assert_eq!(KModule::account_kitties(&1)[0].1.cooldown_until, 6);
+		assert_noop!(KModule::breed(Origin::signed(1), 1, 2), Error::<Test>::KittyInCooldown);
+
+		System::set_block_number(6);
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+
+		// 第二代的kitty 冷却更久，区块6出生的 kitty 4 冷却到 6 + 5 * (1 + 1)
+		System::set_block_number(11);
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_eq!(KModule::kitties(1, 4).unwrap().cooldown_until, 16);
+		assert_noop!(KModule::breed(Origin::signed(1), 4, 5), Error::<Test>::KittyInCooldown);
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 5));
+		assert_eq!(KModule::kitties(1, 6).unwrap().generation, 1);
+		assert_eq!(KModule::kitties(1, 1).unwrap().cooldown_until, 16);
+	});
+}
+
+// 按旧的存储格式写入kitty
+fn put_old_kitty(owner: u64, kitty_id: u32, dna: [u8; 16]) {
+	let key = [Blake2_128Concat::hash(&owner.encode()), Blake2_128Concat::hash(&kitty_id.encode())].concat();
+	put_storage_value(b"Kitties", b"Kitties", &key, dna);
+}
+
+fn put_old_account_kitties(owner: u64, kitties: Vec<(u32, [u8; 16])>) {
+	put_storage_value(b"Kitties", b"AccountKitties", &Blake2_128Concat::hash(&owner.encode()), kitties);
+}
+
+#[test]
+fn migrate_to_latest_works() {
+	new_test_ext().execute_with(|| {
+		put_old_kitty(1, 1, [1; 16]);
+		put_old_kitty(1, 2, [2; 16]);
+		put_old_kitty(2, 3, [3; 16]);
+		put_old_account_kitties(1, vec![(1, [1; 16]), (2, [2; 16])]);
+		put_old_account_kitties(2, vec![(3, [3; 16])]);
+		put_storage_value(b"Kitties", b"KittyNodeStorage", &[], vec![
+			OldKittyNode { _self: 1u32, companion: None, children: vec![3] },
+			OldKittyNode { _self: 2, companion: None, children: vec![3] },
+			OldKittyNode { _self: 3, companion: Some((1, 2)), children: vec![] },
+		]);
+		assert_eq!(KModule::storage_version(), Releases::V1_0_0);
+
+		System::set_block_number(5);
+		assert!(KModule::on_runtime_upgrade() > 0);
+
+		let kitty = |dna: [u8; 16], generation: u32| Kitty { dna, generation, birth: 5, cooldown_until: 5 };
+		assert_eq!(KModule::kitties(1, 1), Some(kitty([1; 16], 0)));
+		assert_eq!(KModule::kitties(1, 2), Some(kitty([2; 16], 0)));
+		assert_eq!(KModule::kitties(2, 3), Some(kitty([3; 16], 1)));
+		assert_eq!(KModule::account_kitties(&1), vec![(1, kitty([1; 16], 0)), (2, kitty([2; 16], 0))]);
+		assert_eq!(KModule::account_kitties(&2), vec![(3, kitty([3; 16], 1))]);
+
+		// kitty 关系拆成每只kitty 的父母和children
+		assert_eq!(KModule::kitty_parents(1), None);
+		assert_eq!(KModule::kitty_parents(3), Some((1, 2)));
+		assert_eq!(KModule::kitty_children(1), vec![3]);
+		assert_eq!(KModule::kitty_children(2), vec![3]);
+		assert!(KModule::kitty_children(3).is_empty());
+		assert_eq!(get_storage_value::<Vec<OldKittyNode<u32>>>(b"Kitties", b"KittyNodeStorage", &[]), None);
+		assert_eq!(KModule::owned_kitties_count(1), 2);
+		assert_eq!(KModule::owned_kitty(1, 1), Some(2));
+		assert_eq!(KModule::owned_kitty_index(3), 0);
+		assert_eq!(get_storage_value::<Vec<(u32, Kitty<u64>)>>(b"Kitties", b"AccountKitties", &Blake2_128Concat::hash(&1u64.encode())), None);
+		assert_eq!(KModule::storage_version(), Releases::V4_0_0);
+
+		// 已经是最新版本，不会重复迁移
+		assert_eq!(KModule::on_runtime_upgrade(), 0);
+	});
+}
+
+#[test]
+fn transfer_swap_removes_owned_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_eq!(KModule::owned_kitties_count(1), 3);
+
+		// 转走第一只，最后一只补到它的位置
+		assert_ok!(KModule::transfer(Origin::signed(1), 2, 1));
+		assert_eq!(KModule::owned_kitties_count(1), 2);
+		assert_eq!(KModule::owned_kitty(1, 0), Some(3));
+		assert_eq!(KModule::owned_kitty(1, 1), Some(2));
+		assert_eq!(KModule::owned_kitty(1, 2), None);
+		assert_eq!(KModule::owned_kitty_index(3), 0);
+
+		assert_eq!(KModule::owned_kitties_count(2), 1);
+		assert_eq!(KModule::owned_kitty(2, 0), Some(1));
+		assert_eq!(KModule::owned_kitty_index(1), 0);
+		assert_eq!(KModule::account_kitties(&1).into_iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![3, 2]);
+	});
+}
+
+#[test]
+fn create_rolls_back_when_deposit_fails() {
+	new_test_ext().execute_with(|| {
+		// 账户6 没有余额，质押失败时kitty 不能被创建
+		let events = System::events().len();
+		assert_noop!(KModule::create(Origin::signed(6)), Error::<Test>::BalanceNotEnough);
+		assert_eq!(KModule::kitties_count(), 0);
+		assert_eq!(KModule::kitty_owner(1), None);
+		assert_eq!(KModule::owned_kitties_count(6), 0);
+		assert_eq!(KModule::lock_amount(1), None);
+		assert_eq!(System::events().len(), events);
+	});
+}
+
+#[test]
+fn breed_rolls_back_when_deposit_fails() {
+	new_test_ext().execute_with(|| {
+		let _ = Balances::make_free_balance_be(&6, 200);
+		assert_ok!(KModule::create(Origin::signed(6)));
+		assert_ok!(KModule::create(Origin::signed(6)));
+		assert_eq!(Balances::free_balance(&6), 0);
+
+		// 余额不够质押，父母的冷却和小kitty 都要回滚
+		assert_noop!(KModule::breed(Origin::signed(6), 1, 2), Error::<Test>::BalanceNotEnough);
+		assert_eq!(KModule::kitties_count(), 2);
+		assert_eq!(KModule::kitties(6, 1).unwrap().cooldown_until, 1);
+		assert_eq!(KModule::kitty_parents(3), None);
+		assert!(KModule::kitty_children(1).is_empty());
+		assert_eq!(Balances::reserved_balance(&6), 200);
+	});
+}
+
+#[test]
+fn transfer_rolls_back_when_deposit_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		// 质押 100 转到新账户7 低于 existential deposit，转移失败
+		set_existential_deposit(500);
+
+		let events = System::events().len();
+		assert!(KModule::transfer(Origin::signed(1), 7, 1).is_err());
+		assert_eq!(KModule::kitty_owner(1), Some(1));
+		assert!(KModule::kitties(1, 1).is_some());
+		assert_eq!(KModule::kitties(7, 1), None);
+		assert_eq!(KModule::owned_kitty(1, 0), Some(1));
+		assert_eq!(KModule::owned_kitties_count(7), 0);
+		assert_eq!(Balances::free_balance(&1), 9900);
+		assert_eq!(Balances::reserved_balance(&1), 100);
+		assert_eq!(Balances::free_balance(&7), 0);
+		assert_eq!(System::events().len(), events);
+	});
+}
+
+parameter_types! {
+	pub const DepositBase: Balance = 100;
+	pub const DepositPerGeneration: Balance = 50;
+	pub const DepositPerOwned: Balance = 10;
+}
+
+#[test]
+fn progressive_deposit_grows_with_generation_and_owned() {
+	type Deposit = ProgressiveDeposit<DepositBase, DepositPerGeneration, DepositPerOwned>;
+
+	assert_eq!(<Deposit as DepositCalculator<Balance>>::deposit(DepositAction::Create, 0, 0), 100);
+	assert_eq!(<Deposit as DepositCalculator<Balance>>::deposit(DepositAction::Create, 0, 3), 130);
+	assert_eq!(<Deposit as DepositCalculator<Balance>>::deposit(DepositAction::Breed, 2, 3), 230);
+	assert_eq!(<FixedDeposit<DepositBase> as DepositCalculator<Balance>>::deposit(DepositAction::Breed, 5, 5), 100);
+}
+
+#[test]
+fn can_burn() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+		assert_eq!(Balances::reserved_balance(&1), 300);
+
+		assert_noop!(KModule::burn(Origin::signed(2), 1), Error::<Test>::InvalidaKittyId);
+		assert_ok!(KModule::burn(Origin::signed(1), 1));
+
+		assert_eq!(KModule::kitties(1, 1), None);
+		assert_eq!(KModule::kitty_owner(1), None);
+		assert_eq!(KModule::lock_amount(1), None);
+		assert_eq!(KModule::owned_kitties_count(1), 2);
+		assert_eq!(Balances::free_balance(&1), 9800);
+		assert_eq!(Balances::reserved_balance(&1), 200);
+		assert_eq!(last_event(), Event::kitties(RawEvent::Burned(1, 1)));
+
+		// 后代依然引用已死亡的父母
+		assert_eq!(KModule::kitty_parents(3), Some((1, 2)));
+		let lineage = KModule::kitty_lineage(1);
+		assert_eq!(lineage.children, vec![3]);
+		assert!(lineage.deceased);
+
+		// 已死亡的kitty 不能再繁殖或转移
+		assert_noop!(KModule::transfer(Origin::signed(1), 2, 1), Error::<Test>::InvalidaKittyId);
+		assert_noop!(KModule::burn(Origin::signed(1), 1), Error::<Test>::InvalidaKittyId);
+	});
+}
+
+#[test]
+fn owned_kitties_are_capped() {
+	new_test_ext().execute_with(|| {
+		for _ in 0..10 {
+			assert_ok!(KModule::create(Origin::signed(1)));
+		}
+		assert_ok!(KModule::create(Origin::signed(2)));
+		assert_eq!(KModule::owned_kitties_count(1), 10);
+
+		assert_noop!(KModule::create(Origin::signed(1)), Error::<Test>::TooManyKitties);
+		assert_noop!(KModule::breed(Origin::signed(1), 1, 2), Error::<Test>::TooManyKitties);
+		assert_noop!(KModule::transfer(Origin::signed(2), 1, 11), Error::<Test>::TooManyKitties);
+
+		assert_ok!(KModule::list(Origin::signed(2), 11, 500));
+		assert_noop!(KModule::buy(Origin::signed(1), 11, 500), Error::<Test>::TooManyKitties);
+
+		// 转走一只之后又可以了
+		assert_ok!(KModule::transfer(Origin::signed(1), 3, 1));
+		assert_ok!(KModule::buy(Origin::signed(1), 11, 500));
+		assert_eq!(KModule::owned_kitties_count(1), 10);
+	});
+}
+
+#[test]
+fn approved_account_can_transfer_from() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+
+		assert_noop!(KModule::transfer_from(Origin::signed(2), 1, 3, 1), Error::<Test>::NotApproved);
+		assert_noop!(KModule::approve(Origin::signed(2), 1, 2), Error::<Test>::NotApproved);
+
+		assert_ok!(KModule::approve(Origin::signed(1), 1, 2));
+		assert_eq!(KModule::approved(1), Some(2));
+		// 只授权了 1 号kitty
+		assert_noop!(KModule::transfer_from(Origin::signed(2), 1, 3, 2), Error::<Test>::NotApproved);
+		assert_noop!(KModule::transfer_from(Origin::signed(2), 3, 2, 1), Error::<Test>::InvalidaKittyId);
+
+		assert_ok!(KModule::transfer_from(Origin::signed(2), 1, 3, 1));
+		assert_eq!(KModule::kitty_owner(1), Some(3));
+		// 转移之后授权清除
+		assert_eq!(KModule::approved(1), None);
+		assert_noop!(KModule::transfer_from(Origin::signed(2), 3, 2, 1), Error::<Test>::NotApproved);
+	});
+}
+
+#[test]
+fn operator_can_transfer_all_kitties() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+
+		assert_ok!(KModule::set_approval_for_all(Origin::signed(1), 2, true));
+		// 全部授权的操作者也可以授权单只kitty
+		assert_ok!(KModule::approve(Origin::signed(2), 2, 3));
+		assert_ok!(KModule::transfer_from(Origin::signed(2), 1, 4, 1));
+		assert_eq!(KModule::kitty_owner(1), Some(4));
+
+		assert_ok!(KModule::set_approval_for_all(Origin::signed(1), 2, false));
+		assert_noop!(KModule::transfer_from(Origin::signed(2), 1, 4, 2), Error::<Test>::NotApproved);
+		assert_ok!(KModule::transfer_from(Origin::signed(3), 1, 4, 2));
+		assert_eq!(KModule::owned_kitties_count(4), 2);
+	});
+}
+
+#[test]
+fn genesis_kitties_are_minted() {
+	new_test_ext_with_kitties(vec![
+		(1, [1; 16], None),
+		(2, [2; 16], None),
+		(1, [3; 16], Some((1, 2))),
+	]).execute_with(|| {
+		assert_eq!(KModule::kitties_count(), 3);
+		assert_eq!(KModule::owned_kitties_count(1), 2);
+		assert_eq!(KModule::owned_kitty(1, 1), Some(3));
+		assert_eq!(KModule::kitty_owner(2), Some(2));
+		assert_eq!(KModule::kitties(1, 3), Some(Kitty {
+			dna: [3; 16],
+			generation: 1,
+			birth: 0,
+			cooldown_until: 0,
+		}));
+		assert_eq!(KModule::kitty_parents(3), Some((1, 2)));
+		assert_eq!(KModule::kitty_children(2), vec![3]);
+
+		// 质押和 create/breed 一样
+		assert_eq!(KModule::lock_amount(3), Some(100));
+		assert_eq!(Balances::reserved_balance(1), 200);
+		assert_eq!(Balances::reserved_balance(2), 100);
+
+		// 新的kitty id 接着创世kitty 分配
+		assert_ok!(KModule::create(Origin::signed(2)));
+		assert_eq!(KModule::kitty_owner(4), Some(2));
+	});
+}
+
+#[test]
+#[should_panic(expected = "genesis kitty parents must be different kitties")]
+fn genesis_kitty_parents_must_differ() {
+	new_test_ext_with_kitties(vec![
+		(1, [1; 16], None),
+		(1, [2; 16], Some((1, 1))),
+	]);
+}
+
+#[test]
+#[should_panic(expected = "genesis kitty parents must not be related")]
+fn genesis_kitty_parents_must_not_be_related() {
+	new_test_ext_with_kitties(vec![
+		(1, [1; 16], None),
+		(2, [2; 16], None),
+		(1, [3; 16], Some((1, 2))),
+		(1, [4; 16], Some((3, 1))),
+	]);
+}
+
+#[test]
+fn genes_follow_dominance() {
+	let dna1 = [0x30, 0x01, 0x41, 0x02, 0x10, 0x20, 0xf0, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+	let dna2 = [0x52, 0x00, 0x43, 0x03, 0x00, 0x11, 0x80, 0x90, 0, 0, 0, 0, 0, 0, 0, 0];
+	// 0 表示遗传隐性基因
+	let random = [1, 1, 1, 1, 0, 0, 1, 0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0];
+
+	let child = genes::breed(&dna1, &dna2, &random);
+	assert_eq!(child, [0x52, 0x30, 0x41, 0x43, 0x20, 0x11, 0xf0, 0x90, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0, 0xf0]);
+	assert_eq!(genes::decode(&child), KittyTraits {
+		body: Body::MaineCoon,
+		pattern: Pattern::Solid,
+		eye_colour: EyeColour::Amber,
+		rarity: RarityTier::Epic,
+	});
+}
+
+#[test]
+fn can_query_kitty_traits() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+
+		let dna = KModule::kitties(1, 3).unwrap().dna;
+		assert_eq!(KModule::kitty_traits(3), Some(genes::decode(&dna)));
+		assert_eq!(KModule::kitty_traits(4), None);
+	});
+}
+
+#[test]
+fn breed_can_mutate_genes() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+		assert!(System::events().iter().any(|r| r.event == Event::kitties(RawEvent::Born(1, 3, vec![]))));
+
+		// 突变概率 100%，每个特征都不是父母的基因表现的特征
+		set_mutation_rate(Permill::one());
+		assert_ok!(KModule::create(Origin::signed(2)));
+		assert_ok!(KModule::create(Origin::signed(2)));
+		assert_ok!(KModule::breed(Origin::signed(2), 4, 5));
+		assert!(System::events().iter().any(|r| r.event == Event::kitties(RawEvent::Born(2, 6, vec![0, 2, 4, 6]))));
+
+		let dna1 = KModule::kitties(2, 4).unwrap().dna;
+		let dna2 = KModule::kitties(2, 5).unwrap().dna;
+		let child = KModule::kitties(2, 6).unwrap().dna;
+		for offset in [genes::BODY, genes::PATTERN, genes::EYE_COLOUR, genes::RARITY].iter().copied() {
+			let parents = [dna1[offset], dna1[offset + 1], dna2[offset], dna2[offset + 1]];
+			assert!(parents.iter().all(|gene| genes::gene_trait(offset, *gene) != genes::gene_trait(offset, child[offset])));
+		}
+		set_mutation_rate(Permill::zero());
+	});
+}
+
+#[test]
+fn mutation_gives_a_new_trait() {
+	// 父母的基因表现 Persian/Siamese/Sphynx/Ragdoll、Solid/Tabby/Spotted/Calico、
+	// Blue/Green/Amber/Copper、Common/Uncommon/Rare/Epic
+	let dna1 = [0, 1, 0, 1, 0, 1, 0, 128, 0, 0, 0, 0, 0, 0, 0, 0];
+	let dna2 = [2, 3, 2, 3, 2, 3, 192, 240, 0, 0, 0, 0, 0, 0, 0, 0];
+	// roll 都是 0，一定突变；突变的字节和父母不同，但是表现的特征相同
+	let random = [0, 0, 0, 6, 0, 0, 0, 5, 0, 0, 0, 5, 0, 0, 0, 1];
+	let mut child = genes::breed(&dna1, &dna2, &[0; 16]);
+
+	assert_eq!(genes::mutate(&mut child, &dna1, &dna2, &random, Permill::one()), vec![0, 2, 4, 6]);
+	assert_eq!(genes::decode(&child), KittyTraits {
+		body: Body::MaineCoon,
+		pattern: Pattern::Tortoiseshell,
+		eye_colour: EyeColour::Odd,
+		rarity: RarityTier::Legendary,
+	});
+}
+
+#[test]
+fn breed_rejects_close_relatives() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+		System::set_block_number(6);
+		assert_ok!(KModule::breed(Origin::signed(1), 1, 2));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		System::set_block_number(16);
+		// 6 是 1 和 2 的孙子
+		assert_ok!(KModule::breed(Origin::signed(1), 3, 5));
+		System::set_block_number(31);
+
+		// 父母
+		assert_noop!(KModule::breed(Origin::signed(1), 3, 1), Error::<Test>::InbreedingNotAllowed);
+		// 子女
+		assert_noop!(KModule::breed(Origin::signed(1), 1, 3), Error::<Test>::InbreedingNotAllowed);
+		// 兄弟姐妹
+		assert_noop!(KModule::breed(Origin::signed(1), 3, 4), Error::<Test>::InbreedingNotAllowed);
+
+		// 祖父母和姑姑只有检查两代的时候才算近亲
+		set_inbreeding_depth(2);
+		assert_noop!(KModule::breed(Origin::signed(1), 6, 1), Error::<Test>::InbreedingNotAllowed);
+		assert_noop!(KModule::breed(Origin::signed(1), 6, 4), Error::<Test>::InbreedingNotAllowed);
+		set_inbreeding_depth(1);
+		assert_ok!(KModule::breed(Origin::signed(1), 6, 1));
+	});
+}
+
+#[test]
+fn inbreeding_depth_is_bounded() {
+	new_test_ext().execute_with(|| {
+		set_inbreeding_depth(0);
+		assert_eq!(KModule::inbreeding_depth(), 1);
+		set_inbreeding_depth(100);
+		assert_eq!(KModule::inbreeding_depth(), MAX_INBREEDING_DEPTH);
+		set_inbreeding_depth(1);
+	});
+}
+
+#[test]
+fn can_set_metadata() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+
+		assert_noop!(
+			KModule::set_metadata(Origin::signed(2), 1, b"tom".to_vec(), vec![], vec![]),
+			Error::<Test>::InvalidaKittyId
+		);
+		assert_noop!(
+			KModule::set_metadata(Origin::signed(1), 1, b"a long name".to_vec(), vec![], vec![]),
+			Error::<Test>::MetadataTooLong
+		);
+
+		// 3 + 7 + 12 = 22 字节，每字节质押 2
+		assert_ok!(KModule::set_metadata(Origin::signed(1), 1, b"tom".to_vec(), b"a kitty".to_vec(), b"ipfs://image".to_vec()));
+		assert_eq!(KModule::kitty_metadata(1), Some(KittyMetadata {
+			name: b"tom".to_vec(),
+			description: b"a kitty".to_vec(),
+			uri: b"ipfs://image".to_vec(),
+		}));
+		assert_eq!(last_event(), Event::kitties(RawEvent::MetadataSet(1, 1, 44)));
+		assert_eq!(Balances::reserved_balance(1), 144);
+
+		// 重新设置按新的长度质押
+		assert_ok!(KModule::set_metadata(Origin::signed(1), 1, b"tom".to_vec(), vec![], vec![]));
+		assert_eq!(Balances::reserved_balance(1), 106);
+
+		// 全部为空时清除
+		assert_ok!(KModule::set_metadata(Origin::signed(1), 1, vec![], vec![], vec![]));
+		assert_eq!(KModule::kitty_metadata(1), None);
+		assert_eq!(Balances::reserved_balance(1), 100);
+	});
+}
+
+#[test]
+fn metadata_on_transfer_is_configurable() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::set_metadata(Origin::signed(1), 1, b"tom".to_vec(), vec![], vec![]));
+		assert_ok!(KModule::set_metadata(Origin::signed(1), 2, b"jerry".to_vec(), vec![], vec![]));
+
+		// 默认保留，质押转由新的拥有者承担
+		assert_ok!(KModule::transfer(Origin::signed(1), 2, 1));
+		assert_eq!(KModule::kitty_metadata(1).unwrap().name, b"tom".to_vec());
+		assert_eq!(KModule::metadata_deposit(1), Some((2, 6)));
+		assert_eq!(Balances::reserved_balance(1), 100 + 10);
+		assert_eq!(Balances::reserved_balance(2), 100 + 6);
+
+		// 新的拥有者修改时退还自己原来的质押
+		assert_ok!(KModule::set_metadata(Origin::signed(2), 1, b"max".to_vec(), vec![], vec![]));
+		assert_eq!(KModule::metadata_deposit(1), Some((2, 6)));
+		assert_eq!(Balances::reserved_balance(2), 100 + 6);
+
+		set_clear_metadata_on_transfer(true);
+		assert_ok!(KModule::transfer(Origin::signed(1), 2, 2));
+		assert_eq!(KModule::kitty_metadata(2), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 200 + 6);
+	});
+}
+
+#[test]
+fn can_reclaim_metadata_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_noop!(KModule::reclaim_metadata_deposit(Origin::signed(1), 1), Error::<Test>::NoMetadataDeposit);
+		assert_ok!(KModule::set_metadata(Origin::signed(1), 1, b"tom".to_vec(), vec![], vec![]));
+		assert_ok!(KModule::transfer(Origin::signed(1), 2, 1));
+		assert_eq!(Balances::reserved_balance(1), 0);
+
+		// 原来的拥有者不能再删除新的拥有者的 metadata
+		assert_noop!(KModule::reclaim_metadata_deposit(Origin::signed(1), 1), Error::<Test>::InvalidaKittyId);
+
+		// 拥有者取回质押，metadata 一起删除
+		assert_ok!(KModule::reclaim_metadata_deposit(Origin::signed(2), 1));
+		assert_eq!(last_event(), Event::kitties(RawEvent::MetadataCleared(2, 1)));
+		assert_eq!(Balances::reserved_balance(2), 100);
+		assert_eq!(KModule::kitty_metadata(1), None);
+	});
+}
+
+#[test]
+fn metadata_is_cleared_when_new_owner_can_not_pay_deposit() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::set_metadata(Origin::signed(1), 1, b"tom".to_vec(), vec![], vec![]));
+
+		// 账户6 只有转过来的kitty 质押，没有余额承担 metadata 的质押
+		assert_ok!(KModule::transfer(Origin::signed(1), 6, 1));
+		assert_eq!(KModule::kitty_metadata(1), None);
+		assert_eq!(KModule::metadata_deposit(1), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(6), 100);
+	});
+}
+
+#[test]
+fn can_rent_kitty() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(2)));
+
+		assert_noop!(KModule::rent(Origin::signed(2), 1, 300), Error::<Test>::RentalOfferNotExist);
+		assert_noop!(KModule::offer_rental(Origin::signed(1), 1, 300, 0), Error::<Test>::InvalidRentalDuration);
+		assert_ok!(KModule::offer_rental(Origin::signed(1), 1, 300, 10));
+		assert_noop!(KModule::rent(Origin::signed(1), 1, 300), Error::<Test>::BuyOwnKitty);
+
+		assert_ok!(KModule::rent(Origin::signed(2), 1, 300));
+		assert_eq!(last_event(), Event::kitties(RawEvent::Rented(1, 2, 1, 300, 11)));
+		assert_eq!(Balances::free_balance(1), 10000 - 100 + 300);
+		assert_eq!(KModule::kitty_owner(1), Some(1));
+		assert_eq!(KModule::custodian(1), Some(2));
+
+		// 拥有者和租借者都不能转移或者出售
+		assert_noop!(KModule::transfer(Origin::signed(1), 3, 1), Error::<Test>::KittyRented);
+		assert_noop!(KModule::transfer(Origin::signed(2), 3, 1), Error::<Test>::KittyRented);
+		assert_noop!(KModule::list(Origin::signed(1), 1, 500), Error::<Test>::KittyRented);
+		assert_noop!(KModule::burn(Origin::signed(1), 1), Error::<Test>::KittyRented);
+
+		// 租借者可以繁殖，小kitty 归租借者，拥有者不行
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_noop!(KModule::breed(Origin::signed(1), 1, 3), Error::<Test>::KittyRented);
+		assert_ok!(KModule::breed(Origin::signed(2), 1, 2));
+		assert_eq!(KModule::kitty_owner(4), Some(2));
+		assert_eq!(KModule::kitties(1, 1).unwrap().cooldown_until, 6);
+
+		// 到期自动归还
+		KModule::on_initialize(11);
+		assert_eq!(last_event(), Event::kitties(RawEvent::RentalEnded(1, 2, 1)));
+		assert_eq!(KModule::custodian(1), Some(1));
+		assert_ok!(KModule::transfer(Origin::signed(1), 3, 1));
+	});
+}
+
+#[test]
+fn rent_rejects_raised_price() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::offer_rental(Origin::signed(1), 1, 300, 10));
+
+		// 拥有者抢先用更高的租金重新报价
+		assert_ok!(KModule::offer_rental(Origin::signed(1), 1, 900, 10));
+		assert_noop!(KModule::rent(Origin::signed(2), 1, 300), Error::<Test>::PriceTooHigh);
+		assert_ok!(KModule::rent(Origin::signed(2), 1, 900));
+		assert_eq!(Balances::free_balance(2), 11000 - 900);
+	});
+}
+
+#[test]
+fn rentals_ending_in_a_block_are_capped() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::offer_rental(Origin::signed(1), 1, 300, 10));
+		assert_ok!(KModule::offer_rental(Origin::signed(1), 2, 300, 10));
+		assert_ok!(KModule::offer_rental(Origin::signed(1), 3, 300, 10));
+
+		assert_ok!(KModule::rent(Origin::signed(2), 1, 300));
+		assert_ok!(KModule::rent(Origin::signed(2), 2, 300));
+		assert_noop!(KModule::rent(Origin::signed(2), 3, 300), Error::<Test>::TooManyRentals);
+		System::set_block_number(2);
+		assert_ok!(KModule::rent(Origin::signed(2), 3, 300));
+	});
+}
+
+#[test]
+fn staked_kitty_earns_rewards() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_eq!(KModule::kitty_traits(1).unwrap().rarity, RarityTier::Rare);
+
+		// 稀有的kitty 每个区块 10 * 4
+		assert_ok!(KModule::stake(Origin::signed(1), 1));
+		assert_eq!(last_event(), Event::kitties(RawEvent::Staked(1, 1, 40)));
+		assert_noop!(KModule::stake(Origin::signed(1), 1), Error::<Test>::KittyStaked);
+		assert_noop!(KModule::stake(Origin::signed(2), 2), Error::<Test>::InvalidaKittyId);
+
+		// 质押期间不能转移、出售或者繁殖
+		assert_noop!(KModule::transfer(Origin::signed(1), 2, 1), Error::<Test>::KittyStaked);
+		assert_noop!(KModule::list(Origin::signed(1), 1, 500), Error::<Test>::KittyStaked);
+		assert_noop!(KModule::breed(Origin::signed(1), 1, 2), Error::<Test>::KittyStaked);
+
+		// 奖励池余额不足时不能领取，奖励保留
+		System::set_block_number(11);
+		assert_noop!(KModule::claim_rewards(Origin::signed(1)), Error::<Test>::RewardPoolExhausted);
+		let _ = Balances::deposit_creating(&KModule::account_id(), 10000);
+		assert_ok!(KModule::claim_rewards(Origin::signed(1)));
+		assert_eq!(last_event(), Event::kitties(RawEvent::RewardsClaimed(1, 400)));
+		assert_eq!(Balances::free_balance(1), 10000 - 200 + 400);
+		assert_noop!(KModule::claim_rewards(Origin::signed(1)), Error::<Test>::NoRewards);
+
+		// 取消质押之后不再产生奖励，之前的奖励还可以领取
+		System::set_block_number(16);
+		assert_ok!(KModule::unstake(Origin::signed(1), 1));
+		assert_noop!(KModule::unstake(Origin::signed(1), 1), Error::<Test>::KittyNotStaked);
+		System::set_block_number(30);
+		assert_ok!(KModule::claim_rewards(Origin::signed(1)));
+		assert_eq!(last_event(), Event::kitties(RawEvent::RewardsClaimed(1, 200)));
+		assert_ok!(KModule::transfer(Origin::signed(1), 2, 1));
+	});
+}
+
+#[test]
+fn root_can_fund_reward_pool() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create(Origin::signed(1)));
+		assert_ok!(KModule::stake(Origin::signed(1), 1));
+		System::set_block_number(11);
+
+		// 只有 root 可以向奖励池增发资金，之后可以领取奖励
+		assert_noop!(KModule::fund_rewards(Origin::signed(1), 10000), BadOrigin);
+		let issuance = Balances::total_issuance();
+		assert_ok!(KModule::fund_rewards(Origin::root(), 10000));
+		assert_eq!(last_event(), Event::kitties(RawEvent::RewardPoolFunded(10000)));
+		assert_eq!(Balances::total_issuance(), issuance + 10000);
+		assert_eq!(Balances::free_balance(KModule::account_id()), 10000);
+		assert_ok!(KModule::claim_rewards(Origin::signed(1)));
+		assert_eq!(Balances::free_balance(KModule::account_id()), 10000 - 400);
+	});
+}
+
+#[test]
+fn can_batch_create_transfer_and_breed() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(KModule::create_many(Origin::signed(1), 4), Error::<Test>::BatchTooLarge);
+		assert_ok!(KModule::create_many(Origin::signed(1), 3));
+		assert_ok!(KModule::create_many(Origin::signed(1), 3));
+		assert_eq!(KModule::owned_kitties_count(1), 6);
+		assert_eq!(Balances::reserved_balance(1), 600);
+		// 同一个交易里创建的kitty DNA 不一样
+		assert_ne!(KModule::kitties(1, 1).unwrap().dna, KModule::kitties(1, 2).unwrap().dna);
+
+		assert_ok!(KModule::breed_many(Origin::signed(1), vec![(1, 2), (3, 4)]));
+		assert_eq!(KModule::kitty_parents(8), Some((3, 4)));
+
+		assert_ok!(KModule::transfer_many(Origin::signed(1), vec![(2, 5), (3, 6)]));
+		assert_eq!(KModule::kitty_owner(5), Some(2));
+		assert_eq!(KModule::kitty_owner(6), Some(3));
+		assert_eq!(last_event(), Event::kitties(RawEvent::Transfered(1, 3, 6)));
+	});
+}
+
+#[test]
+fn batch_is_all_or_nothing() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(KModule::create_many(Origin::signed(1), 2));
+
+		// 第二只不是自己的，第一只也不转
+		assert_noop!(
+			KModule::transfer_many(Origin::signed(1), vec![(2, 1), (2, 3)]),
+			Error::<Test>::InvalidaKittyId
+		);
+		assert_eq!(KModule::kitty_owner(1), Some(1));
+
+		// 第二对在冷却中，第一只小kitty 也不生
+		assert_noop!(
+			KModule::breed_many(Origin::signed(1), vec![(1, 2), (1, 2)]),
+			Error::<Test>::KittyInCooldown
+		);
+		assert_eq!(KModule::kitties_count(), 2);
+
+		// 余额只够一只
+		set_existential_deposit(1);
+		assert_ok!(Balances::transfer(Origin::signed(2), 6, 150));
+		assert_noop!(KModule::create_many(Origin::signed(6), 2), Error::<Test>::BalanceNotEnough);
+		assert_eq!(KModule::owned_kitties_count(6), 0);
+	});
+}
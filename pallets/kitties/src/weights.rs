@@ -0,0 +1,241 @@
+//! Placeholder weights for pallet_kitties.
+//!
+//! These numbers were NOT produced by the benchmarking CLI. The execution times are rough
+//! estimates and the database reads/writes are counted by hand from the dispatchables, so keep
+//! them in sync when storage access changes. Replace them with real output on reference
+//! hardware before production use: `make benchmark` runs every benchmark in benchmarking.rs
+//! and writes the generated `WeightInfo<T>` to runtime/src/weights/pallet_kitties.rs, for the
+//! runtime to use instead of `()`.
+//!
+//! No call takes the number of kitties of an owner as a parameter: OwnedKitties is an indexed
+//! map with swap-remove, so adding or removing a kitty costs the same whatever the owner has.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::weights::{Weight, constants::RocksDbWeight as DbWeight};
+
+/// KittyParents reads made by the inbreeding check: each parent reads 2^d - 1 ancestors.
+fn ancestor_reads(d: u32) -> Weight {
+	(2 as Weight).saturating_mul((1 as Weight).checked_shl(d).unwrap_or(Weight::max_value()).saturating_sub(1))
+}
+
+/// Weight functions needed for pallet_kitties.
+pub trait WeightInfo {
+	fn slash_deposit() -> Weight;
+	fn create() -> Weight;
+	fn create_many(n: u32) -> Weight;
+	fn transfer() -> Weight;
+	fn transfer_many(n: u32) -> Weight;
+	fn approve() -> Weight;
+	fn set_approval_for_all() -> Weight;
+	fn transfer_from() -> Weight;
+	fn set_metadata(b: u32) -> Weight;
+	fn burn() -> Weight;
+	fn list() -> Weight;
+	fn unlist() -> Weight;
+	fn buy() -> Weight;
+	fn start_auction() -> Weight;
+	fn bid() -> Weight;
+	fn start_dutch_auction() -> Weight;
+	fn cancel_dutch_auction() -> Weight;
+	fn buy_dutch() -> Weight;
+	fn breed(d: u32) -> Weight;
+	fn breed_many(n: u32, d: u32) -> Weight;
+	fn offer_sire() -> Weight;
+	fn withdraw_sire_offer() -> Weight;
+	fn breed_with_sire(d: u32) -> Weight;
+	fn offer_rental() -> Weight;
+	fn cancel_rental_offer() -> Weight;
+	fn rent() -> Weight;
+	fn stake() -> Weight;
+	fn unstake() -> Weight;
+	fn claim_rewards() -> Weight;
+	fn reclaim_metadata_deposit() -> Weight;
+	fn fund_rewards() -> Weight;
+	fn on_finalize(a: u32) -> Weight;
+	fn return_rentals(r: u32) -> Weight;
+}
+
+impl WeightInfo for () {
+	fn slash_deposit() -> Weight {
+		(80_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn create() -> Weight {
+		(95_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().writes(8 as Weight))
+	}
+	fn create_many(n: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((90_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads((5 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes((8 as Weight).saturating_mul(n as Weight)))
+	}
+	fn transfer() -> Weight {
+		(160_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().writes(21 as Weight))
+	}
+	fn approve() -> Weight {
+		(32_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn set_approval_for_all() -> Weight {
+		(25_000_000 as Weight)
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn transfer_from() -> Weight {
+		(170_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(16 as Weight))
+			.saturating_add(DbWeight::get().writes(21 as Weight))
+	}
+	fn set_metadata(b: u32) -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add((2_000 as Weight).saturating_mul(b as Weight))
+			.saturating_add(DbWeight::get().reads(4 as Weight))
+			.saturating_add(DbWeight::get().writes(4 as Weight))
+	}
+	fn transfer_many(n: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((155_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads((13 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes((21 as Weight).saturating_mul(n as Weight)))
+	}
+	fn burn() -> Weight {
+		(70_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(12 as Weight))
+			.saturating_add(DbWeight::get().writes(18 as Weight))
+	}
+	fn list() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn unlist() -> Weight {
+		(28_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn buy() -> Weight {
+		(210_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(15 as Weight))
+			.saturating_add(DbWeight::get().writes(22 as Weight))
+	}
+	fn start_auction() -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn bid() -> Weight {
+		(80_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn start_dutch_auction() -> Weight {
+		(42_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn cancel_dutch_auction() -> Weight {
+		(27_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn buy_dutch() -> Weight {
+		(215_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(14 as Weight))
+			.saturating_add(DbWeight::get().writes(23 as Weight))
+	}
+	fn breed(d: u32) -> Weight {
+		(130_000_000 as Weight)
+			.saturating_add((5_000_000 as Weight).saturating_mul(ancestor_reads(d)))
+			.saturating_add(DbWeight::get().reads(13 as Weight))
+			.saturating_add(DbWeight::get().reads(ancestor_reads(d)))
+			.saturating_add(DbWeight::get().writes(13 as Weight))
+	}
+	fn breed_many(n: u32, d: u32) -> Weight {
+		(10_000_000 as Weight)
+			.saturating_add((125_000_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add((5_000_000 as Weight).saturating_mul(ancestor_reads(d)).saturating_mul(n as Weight))
+			.saturating_add(DbWeight::get().reads((13 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().reads(ancestor_reads(d).saturating_mul(n as Weight)))
+			.saturating_add(DbWeight::get().writes((13 as Weight).saturating_mul(n as Weight)))
+	}
+	fn offer_sire() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn withdraw_sire_offer() -> Weight {
+		(28_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn breed_with_sire(d: u32) -> Weight {
+		(190_000_000 as Weight)
+			.saturating_add((5_000_000 as Weight).saturating_mul(ancestor_reads(d)))
+			.saturating_add(DbWeight::get().reads(15 as Weight))
+			.saturating_add(DbWeight::get().reads(ancestor_reads(d)))
+			.saturating_add(DbWeight::get().writes(14 as Weight))
+	}
+	fn offer_rental() -> Weight {
+		(32_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(5 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn cancel_rental_offer() -> Weight {
+		(28_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+	}
+	fn rent() -> Weight {
+		(95_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(7 as Weight))
+			.saturating_add(DbWeight::get().writes(7 as Weight))
+	}
+	fn stake() -> Weight {
+		(60_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(6 as Weight))
+			.saturating_add(DbWeight::get().writes(5 as Weight))
+	}
+	fn unstake() -> Weight {
+		(40_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn claim_rewards() -> Weight {
+		(75_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn reclaim_metadata_deposit() -> Weight {
+		(45_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(3 as Weight))
+			.saturating_add(DbWeight::get().writes(3 as Weight))
+	}
+	fn fund_rewards() -> Weight {
+		(30_000_000 as Weight)
+			.saturating_add(DbWeight::get().reads(2 as Weight))
+			.saturating_add(DbWeight::get().writes(2 as Weight))
+	}
+	fn on_finalize(a: u32) -> Weight {
+		(5_000_000 as Weight)
+			.saturating_add((230_000_000 as Weight).saturating_mul(a as Weight))
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().reads((14 as Weight).saturating_mul(a as Weight)))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+			.saturating_add(DbWeight::get().writes((23 as Weight).saturating_mul(a as Weight)))
+	}
+	fn return_rentals(r: u32) -> Weight {
+		(3_000_000 as Weight)
+			.saturating_add((22_000_000 as Weight).saturating_mul(r as Weight))
+			.saturating_add(DbWeight::get().reads(1 as Weight))
+			.saturating_add(DbWeight::get().reads((2 as Weight).saturating_mul(r as Weight)))
+			.saturating_add(DbWeight::get().writes(1 as Weight))
+			.saturating_add(DbWeight::get().writes((1 as Weight).saturating_mul(r as Weight)))
+	}
+}
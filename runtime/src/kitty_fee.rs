@@ -0,0 +1,441 @@
+//! Transaction payment with kitty based fee discounts.
+//!
+//! `ChargeKittyTransactionPayment` replaces `ChargeTransactionPayment` in `SignedExtra`:
+//!
+//! - a `Kitties::transfer` of a kitty the sender bought within the last `FreeTransferPeriod`
+//!   blocks is fee-less (the tip is still paid). The fee is withdrawn up front and only refunded
+//!   once the transfer succeeds, so a transfer that fails still pays;
+//! - a sender naming a kitty they own with at least `PremiumRarity` in `premium_kitty` gets
+//!   `PremiumFeeDiscount` off the fee (the tip is not discounted);
+//! - everything else is charged exactly like `ChargeTransactionPayment`.
+//!
+//! The storage reads done to look up a discount are added to the weight the fee is computed
+//! from, so naming a `premium_kitty` is never free.
+
+use codec::{Encode, Decode};
+use frame_support::{
+	traits::{Currency, ExistenceRequirement, Get, Imbalance, OnUnbalanced, WithdrawReason},
+	weights::{DispatchInfo, PostDispatchInfo, Weight},
+};
+use sp_runtime::{
+	traits::{DispatchInfoOf, PostDispatchInfoOf, SignedExtension, Saturating, Zero},
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionValidity, TransactionValidityError, ValidTransaction,
+	},
+	DispatchResult, Perbill, SaturatedConversion,
+};
+use pallet_transaction_payment::ChargeTransactionPayment;
+
+use crate::{
+	AccountId, Balance, Balances, Call, FreeTransferPeriod, KittyIndex, Kitties, PremiumFeeDiscount,
+	PremiumRarity, Runtime, System, TransactionPayment,
+};
+
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+type InnerPre = <ChargeTransactionPayment<Runtime> as SignedExtension>::Pre;
+
+/// Fee payment of a discounted transaction: tip, payer, withdrawn fee, discount if the call fails,
+/// discount if the call succeeds.
+pub type DiscountedPre = (Balance, AccountId, Option<NegativeImbalance>, Perbill, Perbill);
+
+/// Charge the transaction fee, with a discount for premium kitty owners and fee-less
+/// transfers of recently bought kitties.
+#[derive(Encode, Decode, Clone, Eq, PartialEq)]
+pub struct ChargeKittyTransactionPayment {
+	/// Tip for the block author, never discounted.
+	#[codec(compact)]
+	tip: Balance,
+	/// A kitty owned by the sender to claim the premium discount with.
+	premium_kitty: Option<KittyIndex>,
+}
+
+impl ChargeKittyTransactionPayment {
+	/// Pay `tip` on top of the fee, claiming the premium discount with `premium_kitty`.
+	pub fn new(tip: Balance, premium_kitty: Option<KittyIndex>) -> Self {
+		Self { tip, premium_kitty }
+	}
+
+	/// Weight of the storage reads `discount` does for `call`. The premium kitty costs its owner
+	/// and the kitty itself, a `Kitties::transfer` costs the purchase block of the transferred
+	/// kitty, its owner is already counted in the weight of the call.
+	fn lookup_weight(&self, call: &Call) -> Weight {
+		let mut reads = 0;
+		if self.premium_kitty.is_some() {
+			reads += 2;
+		}
+		if let Call::Kitties(pallet_kitties::Call::transfer(..)) = call {
+			reads += 1;
+		}
+		<Runtime as frame_system::Trait>::DbWeight::get().reads(reads)
+	}
+
+	/// Part of the fee waived for `who` dispatching `call`: what is waived whatever the outcome,
+	/// and what is waived once the call succeeds.
+	fn discount(&self, who: &AccountId, call: &Call) -> (Perbill, Perbill) {
+		let discount = self.premium_discount(who);
+		if let Call::Kitties(pallet_kitties::Call::transfer(_, kitty_id)) = call {
+			let owned = Kitties::kitty_owner(kitty_id).as_ref() == Some(who);
+			let recently_bought = Kitties::bought_at(kitty_id).map_or(false, |bought| {
+				System::block_number() <= bought.saturating_add(FreeTransferPeriod::get())
+			});
+			if owned && recently_bought {
+				return (discount, Perbill::one());
+			}
+		}
+
+		(discount, discount)
+	}
+
+	/// Part of the fee waived for the owner of `premium_kitty`.
+	fn premium_discount(&self, who: &AccountId) -> Perbill {
+		if let Some(kitty_id) = self.premium_kitty {
+			let owned = Kitties::kitty_owner(kitty_id).as_ref() == Some(who);
+			let premium = Kitties::kitty_traits(kitty_id)
+				.map_or(false, |traits| traits.rarity >= PremiumRarity::get());
+			if owned && premium {
+				return PremiumFeeDiscount::get();
+			}
+		}
+
+		Perbill::zero()
+	}
+
+	/// The part of `fee` left to pay after the discount, the tip included in `fee` is paid in full.
+	fn discounted_fee(fee: Balance, tip: Balance, discount: Perbill) -> Balance {
+		fee.saturating_sub(discount * fee.saturating_sub(tip))
+	}
+
+	/// Withdraw the discounted fee from `who`, returning the fee and what was withdrawn.
+	fn withdraw_fee(
+		&self,
+		who: &AccountId,
+		info: &DispatchInfoOf<Call>,
+		len: usize,
+		discount: Perbill,
+	) -> Result<(Balance, Option<NegativeImbalance>), TransactionValidityError> {
+		let fee = TransactionPayment::compute_fee(len as u32, info, self.tip);
+		let fee = Self::discounted_fee(fee, self.tip, discount);
+		if fee.is_zero() {
+			return Ok((fee, None));
+		}
+
+		let reason = if self.tip.is_zero() {
+			WithdrawReason::TransactionPayment.into()
+		} else {
+			WithdrawReason::TransactionPayment | WithdrawReason::Tip
+		};
+		match Balances::withdraw(who, fee, reason, ExistenceRequirement::KeepAlive) {
+			Ok(imbalance) => Ok((fee, Some(imbalance))),
+			Err(_) => Err(InvalidTransaction::Payment.into()),
+		}
+	}
+
+	fn inner(&self) -> ChargeTransactionPayment<Runtime> {
+		ChargeTransactionPayment::from(self.tip)
+	}
+}
+
+impl sp_std::fmt::Debug for ChargeKittyTransactionPayment {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "ChargeKittyTransactionPayment<{:?}, {:?}>", self.tip, self.premium_kitty)
+	}
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl SignedExtension for ChargeKittyTransactionPayment {
+	const IDENTIFIER: &'static str = "ChargeKittyTransactionPayment";
+	type AccountId = AccountId;
+	type Call = Call;
+	type AdditionalSigned = ();
+	/// Either the inner `ChargeTransactionPayment` took the fee, or it was discounted here.
+	/// The weight of the discount lookups comes first, it is charged in either case.
+	type Pre = (Weight, Option<InnerPre>, Option<DiscountedPre>);
+
+	fn additional_signed(&self) -> Result<(), TransactionValidityError> { Ok(()) }
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> TransactionValidity {
+		let info = &with_lookup_weight(info, self.lookup_weight(call));
+		let (discount, on_success) = self.discount(who, call);
+		if on_success.is_zero() {
+			return self.inner().validate(who, call, info, len);
+		}
+
+		let (fee, _) = self.withdraw_fee(who, info, len, discount)?;
+		let mut r = ValidTransaction::default();
+		r.priority = fee.saturated_into::<TransactionPriority>();
+		Ok(r)
+	}
+
+	fn pre_dispatch(
+		self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: &DispatchInfoOf<Self::Call>,
+		len: usize,
+	) -> Result<Self::Pre, TransactionValidityError> {
+		let lookup_weight = self.lookup_weight(call);
+		let info = &with_lookup_weight(info, lookup_weight);
+		let (discount, on_success) = self.discount(who, call);
+		if on_success.is_zero() {
+			let pre = self.inner().pre_dispatch(who, call, info, len)?;
+			return Ok((lookup_weight, Some(pre), None));
+		}
+
+		let (_, imbalance) = self.withdraw_fee(who, info, len, discount)?;
+		Ok((lookup_weight, None, Some((self.tip, who.clone(), imbalance, discount, on_success))))
+	}
+
+	fn post_dispatch(
+		pre: Self::Pre,
+		info: &DispatchInfoOf<Self::Call>,
+		post_info: &PostDispatchInfoOf<Self::Call>,
+		len: usize,
+		result: &DispatchResult,
+	) -> Result<(), TransactionValidityError> {
+		let (lookup_weight, inner_pre, discounted_pre) = pre;
+		let info = &with_lookup_weight(info, lookup_weight);
+		let post_info = &PostDispatchInfo {
+			actual_weight: post_info.actual_weight.map(|weight| weight.saturating_add(lookup_weight)),
+			pays_fee: post_info.pays_fee,
+		};
+		if let Some(pre) = inner_pre {
+			return ChargeTransactionPayment::<Runtime>::post_dispatch(pre, info, post_info, len, result);
+		}
+
+		if let Some((tip, who, Some(payed), discount, on_success)) = discounted_pre {
+			// Refund the unused weight and, if the call succeeded, the discount it was waiting on.
+			let discount = if result.is_ok() { on_success } else { discount };
+			let actual_fee = TransactionPayment::compute_actual_fee(len as u32, info, post_info, tip);
+			let actual_fee = Self::discounted_fee(actual_fee, tip, discount);
+			let refund = payed.peek().saturating_sub(actual_fee);
+			let actual_payment = match Balances::deposit_into_existing(&who, refund) {
+				Ok(refund_imbalance) => match payed.offset(refund_imbalance) {
+					Ok(actual_payment) => actual_payment,
+					Err(_) => return Err(InvalidTransaction::Payment.into()),
+				},
+				Err(_) => payed,
+			};
+			let imbalances = actual_payment.split(tip);
+			<Runtime as pallet_transaction_payment::Trait>::OnTransactionPayment::on_unbalanceds(
+				Some(imbalances.0).into_iter().chain(Some(imbalances.1))
+			);
+		}
+		Ok(())
+	}
+}
+
+/// `info` with the weight of the discount lookups added.
+fn with_lookup_weight(info: &DispatchInfo, lookup_weight: Weight) -> DispatchInfo {
+	DispatchInfo { weight: info.weight.saturating_add(lookup_weight), ..*info }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::{assert_ok, weights::Pays};
+	use sp_runtime::{BuildStorage, traits::Dispatchable};
+	use crate::{GenesisConfig, Origin};
+
+	const LEN: usize = 10;
+
+	fn alice() -> AccountId { AccountId::from([1u8; 32]) }
+	fn bob() -> AccountId { AccountId::from([2u8; 32]) }
+
+	// Alice owns a legendary kitty (1) and a common one (2), Bob owns a common one (3).
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut legendary = [0u8; 16];
+		legendary[pallet_kitties::genes::RARITY] = 0xff;
+		let storage = GenesisConfig {
+			frame_system: Some(Default::default()),
+			pallet_balances: Some(crate::BalancesConfig {
+				balances: vec![(alice(), 1 << 60), (bob(), 1 << 60)],
+			}),
+			pallet_aura: None,
+			pallet_grandpa: None,
+			pallet_sudo: None,
+			pallet_kitties: Some(crate::KittiesConfig {
+				kitties: vec![
+					(alice(), legendary, None),
+					(alice(), [0u8; 16], None),
+					(bob(), [0u8; 16], None),
+				],
+			}),
+		}.build_storage().unwrap();
+		let mut ext = sp_io::TestExternalities::new(storage);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	fn info_from_weight(weight: Weight) -> DispatchInfo {
+		DispatchInfo { weight, ..Default::default() }
+	}
+
+	fn balance_call() -> Call {
+		Call::Balances(pallet_balances::Call::transfer(bob(), 1_000))
+	}
+
+	fn transfer_kitty_call(kitty_id: KittyIndex) -> Call {
+		Call::Kitties(pallet_kitties::Call::transfer(bob(), kitty_id))
+	}
+
+	// Run the extension around a dispatch and return what the sender paid.
+	fn charged(ext: ChargeKittyTransactionPayment, who: &AccountId, call: &Call) -> Balance {
+		let info = info_from_weight(1_000);
+		let before = Balances::free_balance(who);
+		let pre = ext.pre_dispatch(who, call, &info, LEN).unwrap();
+		assert_ok!(ChargeKittyTransactionPayment::post_dispatch(
+			pre, &info, &PostDispatchInfo::default(), LEN, &Ok(())
+		));
+		before - Balances::free_balance(who)
+	}
+
+	// Like `charged`, but dispatch `call` so the extension sees its real result.
+	fn charged_dispatch(ext: ChargeKittyTransactionPayment, who: &AccountId, call: Call) -> Balance {
+		let info = info_from_weight(1_000);
+		let before = Balances::free_balance(who);
+		let pre = ext.pre_dispatch(who, &call, &info, LEN).unwrap();
+		let (post_info, result) = match call.dispatch(Origin::signed(who.clone())) {
+			Ok(post_info) => (post_info, Ok(())),
+			Err(err) => (err.post_info, Err(err.error)),
+		};
+		assert_ok!(ChargeKittyTransactionPayment::post_dispatch(pre, &info, &post_info, LEN, &result));
+		before - Balances::free_balance(who)
+	}
+
+	fn full_fee(tip: Balance) -> Balance {
+		fee_with_reads(0, tip)
+	}
+
+	// The fee of a call that also pays for `reads` discount lookups.
+	fn fee_with_reads(reads: Weight, tip: Balance) -> Balance {
+		let weight = 1_000 + <Runtime as frame_system::Trait>::DbWeight::get().reads(reads);
+		TransactionPayment::compute_fee(LEN as u32, &info_from_weight(weight), tip)
+	}
+
+	#[test]
+	fn charges_full_fee_without_discount() {
+		new_test_ext().execute_with(|| {
+			let fee = full_fee(0);
+			assert!(fee > 0);
+			assert_eq!(charged(ChargeKittyTransactionPayment::new(0, None), &alice(), &balance_call()), fee);
+			// A common kitty or someone else's kitty gives no discount, but the lookup is still paid.
+			let fee = fee_with_reads(2, 0);
+			assert!(fee > full_fee(0));
+			assert_eq!(charged(ChargeKittyTransactionPayment::new(0, Some(2)), &alice(), &balance_call()), fee);
+			assert_eq!(charged(ChargeKittyTransactionPayment::new(0, Some(1)), &bob(), &balance_call()), fee);
+		});
+	}
+
+	#[test]
+	fn premium_kitty_owner_gets_discount() {
+		new_test_ext().execute_with(|| {
+			let fee = fee_with_reads(2, 0);
+			let discount = PremiumFeeDiscount::get() * fee;
+			assert!(discount > 0);
+			assert_eq!(
+				charged(ChargeKittyTransactionPayment::new(0, Some(1)), &alice(), &balance_call()),
+				fee - discount,
+			);
+
+			// The tip is paid in full.
+			let fee = fee_with_reads(2, 100);
+			assert_eq!(
+				charged(ChargeKittyTransactionPayment::new(100, Some(1)), &alice(), &balance_call()),
+				fee - PremiumFeeDiscount::get() * (fee - 100),
+			);
+		});
+	}
+
+	#[test]
+	fn recently_bought_kitty_transfer_is_fee_less() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Kitties::list(Origin::signed(alice()), 2, 1_000));
+			assert_ok!(Kitties::buy(Origin::signed(bob()), 2, 1_000));
+			assert_ok!(Kitties::list(Origin::signed(alice()), 1, 1_000));
+			assert_ok!(Kitties::buy(Origin::signed(bob()), 1, 1_000));
+
+			let ext = ChargeKittyTransactionPayment::new(0, None);
+			assert_eq!(charged(ext.clone(), &bob(), &transfer_kitty_call(2)), 0);
+			// Only the owner of the bought kitty transfers it for free.
+			assert_eq!(charged(ext.clone(), &alice(), &transfer_kitty_call(2)), fee_with_reads(1, 0));
+			// A kitty owned since genesis is not bought.
+			assert_eq!(charged(ext.clone(), &bob(), &transfer_kitty_call(3)), fee_with_reads(1, 0));
+
+			System::set_block_number(2 + FreeTransferPeriod::get());
+			assert_eq!(charged(ext, &bob(), &transfer_kitty_call(1)), fee_with_reads(1, 0));
+		});
+	}
+
+	#[test]
+	fn failed_transfer_of_bought_kitty_pays_the_fee() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Kitties::list(Origin::signed(alice()), 2, 1_000));
+			assert_ok!(Kitties::buy(Origin::signed(bob()), 2, 1_000));
+			assert_ok!(Kitties::stake(Origin::signed(bob()), 2));
+
+			// A staked kitty can not be transferred, so the fee is kept.
+			let call = Call::Kitties(pallet_kitties::Call::transfer(alice(), 2));
+			let ext = ChargeKittyTransactionPayment::new(0, None);
+			assert_eq!(charged_dispatch(ext.clone(), &bob(), call.clone()), fee_with_reads(1, 0));
+			assert_eq!(Kitties::kitty_owner(2), Some(bob()));
+
+			assert_ok!(Kitties::unstake(Origin::signed(bob()), 2));
+			assert_eq!(charged_dispatch(ext, &bob(), call), 0);
+			assert_eq!(Kitties::kitty_owner(2), Some(alice()));
+		});
+	}
+
+	#[test]
+	fn discounted_transaction_is_validated() {
+		new_test_ext().execute_with(|| {
+			let info = info_from_weight(1_000);
+			let fee = fee_with_reads(2, 0);
+			let ext = ChargeKittyTransactionPayment::new(0, Some(1));
+			let validity = ext.validate(&alice(), &balance_call(), &info, LEN).unwrap();
+			assert_eq!(validity.priority, (fee - PremiumFeeDiscount::get() * fee) as TransactionPriority);
+
+			// An account that can not pay the discounted fee is rejected.
+			let charlie = AccountId::from([3u8; 32]);
+			assert_eq!(
+				ext.validate(&charlie, &balance_call(), &info, LEN),
+				Err(TransactionValidityError::Invalid(InvalidTransaction::Payment)),
+			);
+		});
+	}
+
+	#[test]
+	fn discounted_transaction_refunds_unused_weight() {
+		new_test_ext().execute_with(|| {
+			let info = info_from_weight(1_000);
+			let post_info = PostDispatchInfo { actual_weight: Some(100), pays_fee: Pays::Yes };
+			// The lookup reads are charged on top of the refunded weight.
+			let lookup = <Runtime as frame_system::Trait>::DbWeight::get().reads(2);
+			let actual_fee = TransactionPayment::compute_actual_fee(
+				LEN as u32,
+				&info_from_weight(1_000 + lookup),
+				&PostDispatchInfo { actual_weight: Some(100 + lookup), pays_fee: Pays::Yes },
+				0,
+			);
+			assert!(actual_fee < fee_with_reads(2, 0));
+
+			let before = Balances::free_balance(alice());
+			let ext = ChargeKittyTransactionPayment::new(0, Some(1));
+			let pre = ext.pre_dispatch(&alice(), &balance_call(), &info, LEN).unwrap();
+			assert_ok!(ChargeKittyTransactionPayment::post_dispatch(pre, &info, &post_info, LEN, &Ok(())));
+			assert_eq!(
+				before - Balances::free_balance(alice()),
+				actual_fee - PremiumFeeDiscount::get() * actual_fee,
+			);
+		});
+	}
+}
@@ -9,7 +9,7 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 use sp_std::prelude::*;
 use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
-	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
+	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, ModuleId, MultiSignature,
 	transaction_validity::{TransactionValidity, TransactionSource},
 };
 use sp_runtime::traits::{
@@ -41,6 +41,10 @@ pub use frame_support::{
 /// Import the template pallet.
 pub use pallet_template;
 pub use pallet_kitties;
+use pallet_kitties::genes::RarityTier;
+
+mod kitty_fee;
+pub use kitty_fee::ChargeKittyTransactionPayment;
 
 /// An index to a block.
 pub type BlockNumber = u32;
@@ -62,6 +66,9 @@ pub type Balance = u128;
 /// Index of a transaction in the chain.
 pub type Index = u32;
 
+/// Index of a kitty.
+pub type KittyIndex = u32;
+
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
@@ -96,10 +103,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 30,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 15,
 };
 
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
@@ -269,14 +276,54 @@ impl pallet_template::Trait for Runtime {
 
 parameter_types! {
 	pub const KittyReserveFunds: u64 = 5_000_000_000_000_000;
+	pub const BreedCooldown: BlockNumber = 10 * MINUTES;
+	pub const MaxKittiesPerAccount: u32 = 100;
+	pub const MaxNameLength: u32 = 32;
+	pub const MaxDescriptionLength: u32 = 256;
+	pub const MaxUriLength: u32 = 256;
+	pub const MetadataDepositPerByte: u64 = 10_000_000_000_000;
+	pub const ClearMetadataOnTransfer: bool = false;
+	pub const MaxBatchSize: u32 = 20;
+	pub const MaxAuctionsPerBlock: u32 = 100;
+	pub const MaxRentalsPerBlock: u32 = 100;
+	pub const InbreedingDepth: u32 = 2;
+	pub const MutationRate: Permill = Permill::from_percent(1);
+	/// Staking rewards are paid from the account derived from this id, topped up by `fund_rewards`.
+	pub const KittiesModuleId: ModuleId = ModuleId(*b"py/kitty");
+	pub const StakingRewardPerBlock: Balance = 10_000_000_000;
+}
+
+parameter_types! {
+	/// Owners of a kitty at least this rare get `PremiumFeeDiscount` off their fees.
+	pub const PremiumRarity: RarityTier = RarityTier::Rare;
+	pub const PremiumFeeDiscount: Perbill = Perbill::from_percent(50);
+	/// Transferring a kitty bought within this many blocks is fee-less.
+	pub const FreeTransferPeriod: BlockNumber = 10 * MINUTES;
 }
 
 impl pallet_kitties::Trait for Runtime {
 	type Event = Event;
 	type Randomness = RandomnessCollectiveFlip;
-	type KittyIndex = u32;
+	type KittyIndex = KittyIndex;
 	type Currency = Balances;
-	type KittyReserveFunds = KittyReserveFunds;
+	type MaxKittiesPerAccount = MaxKittiesPerAccount;
+	type DepositCalculator = pallet_kitties::FixedDeposit<KittyReserveFunds>;
+	type BreedCooldown = BreedCooldown;
+	type MaxNameLength = MaxNameLength;
+	type MaxDescriptionLength = MaxDescriptionLength;
+	type MaxUriLength = MaxUriLength;
+	type MetadataDepositPerByte = MetadataDepositPerByte;
+	type ClearMetadataOnTransfer = ClearMetadataOnTransfer;
+	type MaxBatchSize = MaxBatchSize;
+	type MaxAuctionsPerBlock = MaxAuctionsPerBlock;
+	type MaxRentalsPerBlock = MaxRentalsPerBlock;
+	type InbreedingDepth = InbreedingDepth;
+	type MutationRate = MutationRate;
+	type ModuleId = KittiesModuleId;
+	type StakingRewardPerBlock = StakingRewardPerBlock;
+	type SlashOrigin = frame_system::EnsureRoot<AccountId>;
+	type FundOrigin = frame_system::EnsureRoot<AccountId>;
+	type WeightInfo = ();
 }
 
 
@@ -297,7 +344,7 @@ construct_runtime!(
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
 		// Include the custom logic from the template pallet in the runtime.
 		TemplateModule: pallet_template::{Module, Call, Storage, Event<T>},
-		Kitties: pallet_kitties::{Module, Storage, Call, Event<T>},
+		Kitties: pallet_kitties::{Module, Storage, Call, Config<T>, Event<T>},
 	}
 );
 
@@ -319,7 +366,7 @@ pub type SignedExtra = (
 	frame_system::CheckEra<Runtime>,
 	frame_system::CheckNonce<Runtime>,
 	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>
+	ChargeKittyTransactionPayment,
 );
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
@@ -458,6 +505,36 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_kitties_rpc_runtime_api::KittiesApi<Block, AccountId, KittyIndex, BlockNumber> for Runtime {
+		fn account_kitties(account: AccountId) -> Vec<(KittyIndex, pallet_kitties::Kitty<BlockNumber>)> {
+			Kitties::account_kitties(&account)
+		}
+
+		fn kitty(kitty_id: KittyIndex) -> Option<pallet_kitties::Kitty<BlockNumber>> {
+			Kitties::kitty_details(kitty_id)
+		}
+
+		fn kitty_owner(kitty_id: KittyIndex) -> Option<AccountId> {
+			Kitties::kitty_owner(kitty_id)
+		}
+
+		fn kitty_lineage(kitty_id: KittyIndex) -> pallet_kitties::KittyLineage<KittyIndex> {
+			Kitties::kitty_lineage(kitty_id)
+		}
+
+		fn kitty_traits(kitty_id: KittyIndex) -> Option<pallet_kitties::KittyTraits> {
+			Kitties::kitty_traits(kitty_id)
+		}
+
+		fn kitty_metadata(kitty_id: KittyIndex) -> Option<pallet_kitties::KittyMetadata> {
+			Kitties::kitty_metadata(kitty_id)
+		}
+
+		fn kitties_count() -> KittyIndex {
+			Kitties::kitties_count()
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn dispatch_benchmark(
@@ -487,6 +564,7 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
+			add_benchmark!(params, batches, pallet_kitties, Kitties);
 
 			if batches.is_empty() { return Err("Benchmark not found for this pallet.".into()) }
 			Ok(batches)